@@ -0,0 +1,203 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	fmt::{self, Display},
+	net::Ipv6Addr,
+	str::FromStr,
+};
+
+use crate::command::error::CommandError;
+
+pub const DEFAULT_PORT: u16 = 3145;
+
+const SCHEME: &str = "paper://";
+
+#[derive(Clone, PartialEq)]
+pub struct ServerAddr {
+	host: String,
+	port: u16,
+}
+
+impl ServerAddr {
+	pub fn new(host: &str, port: u16) -> Result<Self, CommandError> {
+		let host = host
+			.strip_prefix('[')
+			.and_then(|host| host.strip_suffix(']'))
+			.unwrap_or(host);
+
+		validate_host(host)?;
+
+		if port == 0 {
+			return Err(CommandError::InvalidPort);
+		}
+
+		let addr = ServerAddr {
+			host: host.to_owned(),
+			port,
+		};
+
+		Ok(addr)
+	}
+
+	pub fn port(&self) -> u16 {
+		self.port
+	}
+
+	/// Returns the host as it appears in an address, with IPv6 literals
+	/// enclosed in brackets.
+	pub fn host_literal(&self) -> String {
+		match self.is_ipv6() {
+			true => format!("[{}]", self.host),
+			false => self.host.clone(),
+		}
+	}
+
+	pub fn url(&self) -> String {
+		format!("{SCHEME}{self}")
+	}
+
+	fn is_ipv6(&self) -> bool {
+		self.host.contains(':')
+	}
+}
+
+impl FromStr for ServerAddr {
+	type Err = CommandError;
+
+	/// Parses an address of the form `[paper://]host[:port]`, where an IPv6
+	/// host must be enclosed in brackets if a port is supplied.
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		let value = match value.strip_prefix(SCHEME) {
+			Some(rest) => rest,
+			None if value.contains("://") => {
+				return Err(CommandError::InvalidAddress("unsupported scheme"));
+			},
+			None => value,
+		};
+
+		let value = value.strip_suffix('/').unwrap_or(value);
+
+		if let Some(rest) = value.strip_prefix('[') {
+			let Some((host, rest)) = rest.split_once(']') else {
+				return Err(CommandError::InvalidAddress("unclosed bracket"));
+			};
+
+			let port = match rest {
+				"" => DEFAULT_PORT,
+				rest => match rest.strip_prefix(':') {
+					Some(port) => parse_port(port)?,
+					None => return Err(CommandError::InvalidAddress("unexpected characters")),
+				},
+			};
+
+			return ServerAddr::new(host, port);
+		}
+
+		// more than one colon without brackets can only be a bare IPv6 literal
+		if value.matches(':').count() > 1 {
+			return ServerAddr::new(value, DEFAULT_PORT);
+		}
+
+		match value.split_once(':') {
+			Some((host, port)) => ServerAddr::new(host, parse_port(port)?),
+			None => ServerAddr::new(value, DEFAULT_PORT),
+		}
+	}
+}
+
+impl Display for ServerAddr {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}:{}", self.host_literal(), self.port)
+	}
+}
+
+fn parse_port(value: &str) -> Result<u16, CommandError> {
+	match value.parse::<u16>() {
+		Ok(0) | Err(_) => Err(CommandError::InvalidPort),
+		Ok(port) => Ok(port),
+	}
+}
+
+fn validate_host(host: &str) -> Result<(), CommandError> {
+	if host.is_empty() {
+		return Err(CommandError::InvalidAddress("missing host"));
+	}
+
+	if host.contains(':') {
+		return match Ipv6Addr::from_str(host) {
+			Ok(_) => Ok(()),
+			Err(_) => Err(CommandError::InvalidAddress("malformed IPv6 address")),
+		};
+	}
+
+	let is_valid = host
+		.chars()
+		.all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+
+	match is_valid {
+		true => Ok(()),
+		false => Err(CommandError::InvalidAddress("invalid characters in host")),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+
+	use crate::{
+		addr::ServerAddr,
+		command::error::CommandError,
+	};
+
+	fn parse(addr: &str) -> Result<String, CommandError> {
+		ServerAddr::from_str(addr).map(|addr| addr.to_string())
+	}
+
+	#[test]
+	fn it_parses_hosts() {
+		assert_eq!(parse("localhost"), Ok("localhost:3145".into()));
+		assert_eq!(parse("cache-1.example.com:4000"), Ok("cache-1.example.com:4000".into()));
+		assert_eq!(parse("127.0.0.1:3145"), Ok("127.0.0.1:3145".into()));
+	}
+
+	#[test]
+	fn it_parses_urls() {
+		assert_eq!(parse("paper://localhost"), Ok("localhost:3145".into()));
+		assert_eq!(parse("paper://localhost:4000/"), Ok("localhost:4000".into()));
+
+		assert_eq!(
+			ServerAddr::from_str("paper://[::1]:4000").map(|addr| addr.url()),
+			Ok("paper://[::1]:4000".into()),
+		);
+	}
+
+	#[test]
+	fn it_parses_ipv6_hosts() {
+		assert_eq!(parse("[::1]"), Ok("[::1]:3145".into()));
+		assert_eq!(parse("[::1]:4000"), Ok("[::1]:4000".into()));
+		assert_eq!(parse("fe80::1"), Ok("[fe80::1]:3145".into()));
+	}
+
+	#[test]
+	fn it_rejects_invalid_addresses() {
+		assert_eq!(parse("http://localhost"), Err(CommandError::InvalidAddress("unsupported scheme")));
+		assert_eq!(parse("[::1"), Err(CommandError::InvalidAddress("unclosed bracket")));
+		assert_eq!(parse("[::1]x"), Err(CommandError::InvalidAddress("unexpected characters")));
+		assert_eq!(parse(""), Err(CommandError::InvalidAddress("missing host")));
+		assert_eq!(parse(":3145"), Err(CommandError::InvalidAddress("missing host")));
+		assert_eq!(parse("[1::2::3]"), Err(CommandError::InvalidAddress("malformed IPv6 address")));
+		assert_eq!(parse("日本"), Err(CommandError::InvalidAddress("invalid characters in host")));
+	}
+
+	#[test]
+	fn it_rejects_invalid_ports() {
+		for addr in ["localhost:0", "localhost:65536", "localhost:", "localhost:port", "[::1]:0"] {
+			assert_eq!(parse(addr), Err(CommandError::InvalidPort), "{addr}");
+		}
+	}
+}
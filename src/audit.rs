@@ -0,0 +1,245 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	ffi::CStr,
+	fs::{File, OpenOptions},
+	io::Write,
+	mem::MaybeUninit,
+	path::Path,
+	ptr,
+};
+
+use chrono::{SecondsFormat, Utc};
+use serde_json::{Map, Value};
+
+use crate::command::{ClientCommand, client::Response, error::CommandError};
+
+/// The largest buffer used to look up the name of the user.
+#[cfg(unix)]
+const MAX_PASSWD_BUF_LEN: usize = 1 << 20;
+
+/// An append-only log of the commands which change a cache, with one JSON
+/// object per line.
+pub struct AuditLog {
+	file: File,
+}
+
+/// A change to one key, or to the cache as a whole, which is recorded once
+/// the result of the command is known.
+pub struct AuditEntry {
+	command: &'static str,
+	key:     Option<String>,
+
+	/// The size of the value being set. The value itself is never recorded.
+	value_size: Option<usize>,
+
+	/// The argument of a command which does not operate on a key (e.g., the
+	/// size of a resize).
+	argument: Option<String>,
+}
+
+impl AuditLog {
+	pub fn open(path: &Path) -> Result<Self, CommandError> {
+		let file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(path)
+			.map_err(|_| CommandError::InvalidAuditLog)?;
+
+		Ok(AuditLog {
+			file,
+		})
+	}
+
+	/// Records the entries of a command which was sent to the host, along
+	/// with the result of each one.
+	pub fn record(
+		&mut self,
+		host: &str,
+		entries: Vec<AuditEntry>,
+		result: &Result<Response, CommandError>,
+	) -> Result<(), CommandError> {
+		let time = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+		let user = user();
+
+		let new_size = match result {
+			Ok(Response::Resize(resize)) => Some(resize.new_size()),
+			_ => None,
+		};
+
+		let mut lines = String::new();
+
+		for (index, entry) in entries.into_iter().enumerate() {
+			let result = match result {
+				// the results of a multi-key command are in the order of its keys
+				Ok(Response::Keys(results)) => match results.results().get(index) {
+					Some(key_result) => result_str(key_result.result().map(|_| ())),
+					None => "not sent".into(),
+				},
+
+				Ok(_) => result_str(Ok(())),
+				Err(err) => result_str(Err(err)),
+			};
+
+			let mut value = Map::new();
+
+			value.insert("time".into(), time.clone().into());
+			value.insert("user".into(), user.clone().into());
+			value.insert("host".into(), host.into());
+			value.insert("command".into(), entry.command.into());
+
+			if let Some(key) = entry.key {
+				value.insert("key".into(), key.into());
+			}
+
+			if let Some(value_size) = entry.value_size {
+				value.insert("value_size".into(), value_size.into());
+			}
+
+			// a relative size is only resolved once the cache's current size
+			// is known, so the size it was resized to is recorded instead
+			let argument = new_size
+				.map(|size| size.to_string())
+				.or(entry.argument);
+
+			if let Some(argument) = argument {
+				value.insert("argument".into(), argument.into());
+			}
+
+			value.insert("result".into(), result.into());
+
+			lines += &format!("{}\n", Value::Object(value));
+		}
+
+		// the entries are written at once so that they are not interleaved
+		// with those of another session writing to the same log
+		self.file
+			.write_all(lines.as_bytes())
+			.map_err(|_| CommandError::AuditLogFailed)
+	}
+}
+
+/// Returns an entry for each change the command makes to the cache, or no
+/// entries if it does not change the cache. An edit is recorded once the
+/// edited value is set.
+pub fn entries(command: &ClientCommand) -> Vec<AuditEntry> {
+	match command {
+		ClientCommand::Set(key, value, _) => vec![AuditEntry::key("set", key, Some(value.len()))],
+		ClientCommand::Del(key) => vec![AuditEntry::key("del", key, None)],
+
+		ClientCommand::Ttl(key, ttl) => {
+			let argument = match ttl {
+				Some(ttl) => format!("{ttl}s"),
+				None => "none".into(),
+			};
+
+			vec![AuditEntry::key("ttl", key, None).with_argument(argument)]
+		},
+
+		ClientCommand::MSet(pairs, _) => pairs
+			.iter()
+			.map(|(key, value)| AuditEntry::key("set", key, Some(value.len())))
+			.collect(),
+
+		ClientCommand::MDel(keys) => keys
+			.iter()
+			.map(|key| AuditEntry::key("del", key, None))
+			.collect(),
+
+		ClientCommand::Wipe => vec![AuditEntry::cache("wipe")],
+		ClientCommand::Resize(size) => vec![AuditEntry::cache("resize").with_argument(size.to_string())],
+
+		ClientCommand::Policy(Some(policy)) => {
+			vec![AuditEntry::cache("policy").with_argument(policy.to_string())]
+		},
+
+		_ => Vec::new(),
+	}
+}
+
+impl AuditEntry {
+	fn key(command: &'static str, key: &str, value_size: Option<usize>) -> Self {
+		AuditEntry {
+			command,
+			key: Some(key.to_owned()),
+
+			value_size,
+			argument: None,
+		}
+	}
+
+	fn cache(command: &'static str) -> Self {
+		AuditEntry {
+			command,
+			key: None,
+
+			value_size: None,
+			argument: None,
+		}
+	}
+
+	fn with_argument(mut self, argument: String) -> Self {
+		self.argument = Some(argument);
+		self
+	}
+}
+
+fn result_str(result: Result<(), &CommandError>) -> String {
+	match result {
+		Ok(_) => "ok".into(),
+		Err(err) => format!("error: {err}"),
+	}
+}
+
+/// Returns the name of the user running the CLI, or their uid if it has no
+/// name. The environment is not used, since it can be changed by the user.
+#[cfg(unix)]
+fn user() -> String {
+	// SAFETY: getuid cannot fail
+	let uid = unsafe { libc::getuid() };
+
+	let mut buf: Vec<libc::c_char> = vec![0; 1024];
+
+	loop {
+		let mut passwd = MaybeUninit::<libc::passwd>::uninit();
+		let mut entry: *mut libc::passwd = ptr::null_mut();
+
+		// SAFETY: the buffer outlives the entry, which points into it, and
+		// its length is supplied
+		let code = unsafe {
+			libc::getpwuid_r(
+				uid,
+				passwd.as_mut_ptr(),
+				buf.as_mut_ptr(),
+				buf.len(),
+				&mut entry,
+			)
+		};
+
+		if code == libc::ERANGE && buf.len() < MAX_PASSWD_BUF_LEN {
+			buf.resize(buf.len() * 2, 0);
+			continue;
+		}
+
+		if code != 0 || entry.is_null() {
+			return uid.to_string();
+		}
+
+		// SAFETY: the entry was found, so its name is a valid string in the
+		// buffer
+		let name = unsafe { CStr::from_ptr((*entry).pw_name) };
+
+		return name.to_string_lossy().into_owned();
+	}
+}
+
+/// Returns the name of the user running the CLI.
+#[cfg(not(unix))]
+fn user() -> String {
+	std::env::var("USERNAME").unwrap_or_else(|_| "unknown".into())
+}
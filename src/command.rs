@@ -7,12 +7,33 @@
 
 pub mod cli;
 pub mod client;
+pub mod codec;
+pub mod digest;
 pub mod error;
+pub mod expand;
+pub mod multi;
 pub mod parser;
+pub mod path;
+pub mod resize;
+pub mod ttl;
+pub mod view;
 
 pub use crate::command::{cli::CliCommand, client::ClientCommand};
 
 pub enum Command {
 	Client(ClientCommand),
 	Cli(CliCommand),
+
+	/// A client command sent to the named connection rather than the
+	/// active one (e.g., `@b get key`).
+	Targeted(String, ClientCommand),
+
+	/// The commands produced by expanding the braces in the arguments of
+	/// one command (e.g., `del tmp:{a,b,c}`), along with the number of keys
+	/// they operate on.
+	Expanded(usize, Vec<Command>),
+
+	/// A command which is parsed and routed, but not sent (e.g., `dry del
+	/// tmp:{a,b}`).
+	Dry(Box<Command>),
 }
@@ -15,6 +15,21 @@ pub enum CliCommand {
 	Help,
 	Clear,
 	Quit,
+
+	Connect(String),
+	Disconnect,
+	Reconnect,
+
+	Open(String, String),
+	Use(String),
+	Close(String),
+	Connections,
+
+	Shard(String),
+
+	/// Starts recording the session to the file at the supplied path, or
+	/// stops recording it if no path is supplied.
+	Record(Option<String>),
 }
 
 impl CliCommand {
@@ -26,6 +41,29 @@ impl CliCommand {
 		matches!(self, CliCommand::Help)
 	}
 
+	/// Returns `true` if the command changes which server the CLI is
+	/// connected to.
+	pub fn is_connection(&self) -> bool {
+		matches!(
+			self,
+			CliCommand::Connect(_)
+				| CliCommand::Disconnect
+				| CliCommand::Reconnect
+				| CliCommand::Open(_, _)
+				| CliCommand::Use(_)
+				| CliCommand::Close(_)
+		)
+	}
+
+	/// Returns `true` if the command changes the set of hosts the CLI can
+	/// send commands to, or the address of one of them.
+	pub fn is_host_change(&self) -> bool {
+		matches!(
+			self,
+			CliCommand::Connect(_) | CliCommand::Open(_, _) | CliCommand::Close(_)
+		)
+	}
+
 	pub fn run(&self) -> Result<(), CommandError> {
 		match self {
 			CliCommand::Clear => {
@@ -43,7 +81,17 @@ impl CliCommand {
 				}
 			},
 
-			CliCommand::Help | CliCommand::Quit => Ok(()),
+			CliCommand::Help
+			| CliCommand::Quit
+			| CliCommand::Connect(_)
+			| CliCommand::Disconnect
+			| CliCommand::Reconnect
+			| CliCommand::Open(_, _)
+			| CliCommand::Use(_)
+			| CliCommand::Close(_)
+			| CliCommand::Connections
+			| CliCommand::Shard(_)
+			| CliCommand::Record(_) => Ok(()),
 		}
 	}
 }
@@ -5,149 +5,496 @@
  * LICENSE file in the root directory of this source tree.
  */
 
+use std::str;
+
 use kwik::fmt;
-use paper_client::{PaperClient, PaperClientResult, PaperPolicy, PaperValue};
+use paper_client::{PaperClient, PaperPolicy, PaperValue, Status};
+
+use crate::command::{
+	digest::{Algorithm, Digest},
+	error::CommandError,
+	multi::{self, KeyResults, Outcome},
+	resize::{CacheSize, Resize},
+	view::View,
+};
 
+#[derive(Clone)]
 pub enum ClientCommand {
 	Ping,
 	Version,
 
 	Auth(String),
 
-	Get(String),
-	Set(String, String, Option<u32>),
+	Get(String, View),
+	Set(String, Vec<u8>, Option<u32>),
 	Del(String),
 
 	Has(String),
-	Peek(String),
+	Peek(String, View),
 	Ttl(String, Option<u32>),
 	Size(String),
 
+	/// Fetches the value so that it can be edited and set back with the
+	/// supplied TTL.
+	Edit(String, Option<u32>),
+
+	Digest(String, Algorithm),
+	Diff(String, String),
+
+	MGet(Vec<String>),
+	MSet(Vec<(String, Vec<u8>)>, Option<u32>),
+	MDel(Vec<String>),
+	MHas(Vec<String>),
+
 	Wipe,
 
-	Resize(u64),
-	Policy(PaperPolicy),
+	Resize(CacheSize),
+
+	/// Sets the policy, or shows the current and configured policies if
+	/// none is supplied.
+	Policy(Option<PaperPolicy>),
 
 	Status(bool),
 }
 
-const SUCCESS_MESSAGE: &str = "done";
+/// The typed result of a client command, independent of how it is
+/// displayed.
+pub enum Response {
+	/// The command succeeded without returning a value.
+	Done,
+
+	Bool(bool),
+	Bytes(PaperValue),
+	Size(u32),
+	Status(Status),
+
+	Digest(Digest),
+
+	/// The values of two keys, to be compared.
+	Diff([(String, PaperValue); 2]),
+
+	/// The result for each key of a multi-key command.
+	Keys(KeyResults),
+
+	Resize(Resize),
+
+	/// The status of the cache, of which only the policies are shown.
+	Policies(Status),
+}
+
+impl Response {
+	/// Returns `true` if the command failed for any of its keys.
+	pub fn has_errors(&self) -> bool {
+		matches!(self, Response::Keys(results) if results.count(Outcome::Error) > 0)
+	}
+}
 
 impl ClientCommand {
-	pub fn send(self, client: &mut PaperClient) -> PaperClientResult<PaperValue> {
+	/// Returns the keys the command operates on.
+	pub fn keys(&self) -> Vec<&str> {
 		match self {
-			ClientCommand::Ping => client.ping(),
-			ClientCommand::Version => client.version(),
+			ClientCommand::Diff(a, b) => vec![a, b],
 
-			ClientCommand::Auth(token) => client.auth(token).map(|_| SUCCESS_MESSAGE.into()),
+			ClientCommand::MGet(keys) | ClientCommand::MDel(keys) | ClientCommand::MHas(keys) => {
+				keys.iter().map(String::as_str).collect()
+			},
 
-			ClientCommand::Get(key) => client.get(key),
-			ClientCommand::Set(key, value, ttl) => client
-				.set(key, value, ttl)
-				.map(|_| SUCCESS_MESSAGE.into()),
-			ClientCommand::Del(key) => client.del(key).map(|_| SUCCESS_MESSAGE.into()),
+			ClientCommand::MSet(pairs, _) => pairs.iter().map(|(key, _)| key.as_str()).collect(),
+
+			command => command.key().into_iter().collect(),
+		}
+	}
+
+	/// Returns the word which describes a successful result of a multi-key
+	/// command (e.g., "found" or "deleted").
+	pub fn action(&self) -> Option<&'static str> {
+		match self {
+			ClientCommand::MGet(_) | ClientCommand::MHas(_) => Some("found"),
+			ClientCommand::MSet(..) => Some("set"),
+			ClientCommand::MDel(_) => Some("deleted"),
+
+			_ => None,
+		}
+	}
+
+	/// Splits a multi-key command into one command per node, each of which
+	/// only contains the keys owned by that node. A diff is split into a peek
+	/// of each key, so that the values can be compared locally.
+	pub fn partition(
+		&self,
+		mut node: impl FnMut(&str) -> Result<String, CommandError>,
+	) -> Result<Vec<(String, ClientCommand)>, CommandError> {
+		let parts = match self {
+			ClientCommand::MGet(keys) => group(keys, String::as_str, &mut node)?
+				.into_iter()
+				.map(|(node, keys)| (node, ClientCommand::MGet(keys)))
+				.collect(),
+
+			ClientCommand::MSet(pairs, ttl) => group(pairs, |(key, _)| key, &mut node)?
+				.into_iter()
+				.map(|(node, pairs)| (node, ClientCommand::MSet(pairs, *ttl)))
+				.collect(),
+
+			ClientCommand::MDel(keys) => group(keys, String::as_str, &mut node)?
+				.into_iter()
+				.map(|(node, keys)| (node, ClientCommand::MDel(keys)))
+				.collect(),
+
+			ClientCommand::MHas(keys) => group(keys, String::as_str, &mut node)?
+				.into_iter()
+				.map(|(node, keys)| (node, ClientCommand::MHas(keys)))
+				.collect(),
+
+			ClientCommand::Diff(a, b) => vec![
+				(node(a)?, ClientCommand::Peek(a.clone(), View::default())),
+				(node(b)?, ClientCommand::Peek(b.clone(), View::default())),
+			],
+
+			_ => return Err(CommandError::CrossShardKeys),
+		};
+
+		Ok(parts)
+	}
+
+	/// Returns the key the command operates on, if it operates on exactly
+	/// one key.
+	fn key(&self) -> Option<&str> {
+		match self {
+			ClientCommand::Get(key, _)
+			| ClientCommand::Set(key, _, _)
+			| ClientCommand::Del(key)
+			| ClientCommand::Has(key)
+			| ClientCommand::Peek(key, _)
+			| ClientCommand::Ttl(key, _)
+			| ClientCommand::Size(key)
+			| ClientCommand::Edit(key, _)
+			| ClientCommand::Digest(key, _) => Some(key),
+
+			_ => None,
+		}
+	}
+
+	/// Returns `true` if the command changes the cache.
+	pub fn is_mutating(&self) -> bool {
+		matches!(
+			self,
+			ClientCommand::Set(..)
+				| ClientCommand::Del(_)
+				| ClientCommand::Ttl(..)
+				| ClientCommand::Edit(..)
+				| ClientCommand::MSet(..)
+				| ClientCommand::MDel(_)
+				| ClientCommand::Wipe
+				| ClientCommand::Resize(_)
+				| ClientCommand::Policy(Some(_))
+		)
+	}
+
+	/// Returns `true` if the command can make a destructive change, which
+	/// depends on the status of the cache it is sent to.
+	pub fn is_destructive(&self) -> bool {
+		matches!(
+			self,
+			ClientCommand::Wipe | ClientCommand::Resize(_) | ClientCommand::Policy(Some(_))
+		)
+	}
+
+	/// Returns a description of the change the command makes to a cache with
+	/// the supplied status if it is destructive (i.e., it wipes the cache,
+	/// shrinks it or changes its policy), or `None` otherwise.
+	pub fn change_description(&self, status: &Status) -> Result<Option<String>, CommandError> {
+		let description = match self {
+			ClientCommand::Wipe => Some("Wipe cache".into()),
+
+			ClientCommand::Resize(size) => {
+				let new_size = size.resolve(status.max_size())?;
+
+				(new_size < status.max_size()).then(|| {
+					format!(
+						"Shrink cache from {} to {}",
+						fmt::memory(status.max_size(), Some(2)),
+						fmt::memory(new_size, Some(2)),
+					)
+				})
+			},
+
+			// an auto policy is changed even if it is currently using the
+			// same policy
+			ClientCommand::Policy(Some(policy))
+				if policy != status.policy() || status.is_auto_policy() =>
+			{
+				let current_policy = match status.is_auto_policy() {
+					true => format!("auto({})", status.policy()),
+					false => status.policy().to_string(),
+				};
+
+				Some(format!("Change policy from {current_policy} to {policy}"))
+			},
+
+			_ => None,
+		};
+
+		Ok(description)
+	}
+
+	/// Returns the options describing how the command's value is displayed.
+	pub fn view(&self) -> View {
+		match self {
+			ClientCommand::Get(_, view) | ClientCommand::Peek(_, view) => view.clone(),
+			_ => View::default(),
+		}
+	}
+
+	/// Returns a description of each request the command sends to the
+	/// server, in the order they are sent, without sending them.
+	pub fn requests(&self) -> Vec<String> {
+		match self {
+			ClientCommand::Ping => vec!["ping".into()],
+			ClientCommand::Version => vec!["version".into()],
+
+			// the token is never shown
+			ClientCommand::Auth(_) => vec!["auth ********".into()],
+
+			ClientCommand::Get(key, _) => vec![format!("get {key}")],
+			ClientCommand::Set(key, value, ttl) => vec![set_request(key, value, *ttl)],
+			ClientCommand::Del(key) => vec![format!("del {key}")],
+
+			ClientCommand::Has(key) => vec![format!("has {key}")],
+			ClientCommand::Peek(key, _) => vec![format!("peek {key}")],
+			ClientCommand::Ttl(key, ttl) => vec![format!("ttl {key} {}", ttl_str(*ttl))],
+			ClientCommand::Size(key) => vec![format!("size {key}")],
+
+			ClientCommand::Edit(key, ttl) => vec![
+				format!("peek {key}"),
+				format!("set {key} <edited value>{}", set_ttl_str(*ttl)),
+			],
+
+			ClientCommand::Digest(key, _) => vec![format!("peek {key}")],
+			ClientCommand::Diff(a, b) => vec![format!("peek {a}"), format!("peek {b}")],
+
+			ClientCommand::MGet(keys) => keys.iter().map(|key| format!("get {key}")).collect(),
+
+			ClientCommand::MSet(pairs, ttl) => pairs
+				.iter()
+				.map(|(key, value)| set_request(key, value, *ttl))
+				.collect(),
+
+			ClientCommand::MDel(keys) => keys.iter().map(|key| format!("del {key}")).collect(),
+			ClientCommand::MHas(keys) => keys.iter().map(|key| format!("has {key}")).collect(),
+
+			ClientCommand::Wipe => vec!["wipe".into()],
+
+			ClientCommand::Resize(size) => {
+				let resize = match size {
+					CacheSize::Absolute(size) => {
+						format!("resize {} ({size} B)", fmt::memory(*size, Some(2)))
+					},
 
-			ClientCommand::Has(key) => {
-				let value = format!("{}", client.has(key)?).into();
-				Ok(value)
+					size => format!("resize {size} relative to the current size"),
+				};
+
+				vec!["status".into(), resize]
 			},
 
-			ClientCommand::Peek(key) => client.peek(key),
+			ClientCommand::Policy(Some(policy)) => vec![format!("policy {policy}")],
+			ClientCommand::Policy(None) => vec!["status".into()],
+
+			ClientCommand::Status(_) => vec!["status".into()],
+		}
+	}
+
+	pub fn send(self, client: &mut PaperClient) -> Result<Response, CommandError> {
+		let action = self.action().unwrap_or_default();
+
+		let response = match self {
+			ClientCommand::Ping => Response::Bytes(client.ping()?),
+			ClientCommand::Version => Response::Bytes(client.version()?),
+
+			ClientCommand::Auth(token) => client.auth(token).map(|_| Response::Done)?,
+
+			ClientCommand::Get(key, _) => Response::Bytes(client.get(key)?),
+			ClientCommand::Set(key, value, ttl) => client
+				.set(key, value, ttl)
+				.map(|_| Response::Done)?,
+			ClientCommand::Del(key) => client.del(key).map(|_| Response::Done)?,
+
+			ClientCommand::Has(key) => Response::Bool(client.has(key)?),
+			ClientCommand::Peek(key, _) => Response::Bytes(client.peek(key)?),
 			ClientCommand::Ttl(key, ttl) => client
 				.ttl(key, ttl)
-				.map(|_| SUCCESS_MESSAGE.into()),
+				.map(|_| Response::Done)?,
+			ClientCommand::Size(key) => Response::Size(client.size(key)?),
 
-			ClientCommand::Size(key) => {
-				let size = client.size(key)?;
+			// peek does not count as an access, so editing a value does not
+			// affect the eviction policy
+			ClientCommand::Edit(key, _) => Response::Bytes(client.peek(key)?),
 
-				let value = format!("{} ({} B)", fmt::memory(size, Some(2)), size,).into();
-
-				Ok(value)
+			ClientCommand::Digest(key, algorithm) => {
+				let value = client.peek(&key)?;
+				Response::Digest(Digest::new((&value).into(), algorithm))
 			},
 
-			ClientCommand::Wipe => client.wipe().map(|_| SUCCESS_MESSAGE.into()),
+			ClientCommand::Diff(a, b) => {
+				let a_value = client.peek(&a)?;
+				let b_value = client.peek(&b)?;
 
-			ClientCommand::Resize(size) => client
-				.resize(size)
-				.map(|_| SUCCESS_MESSAGE.into()),
-			ClientCommand::Policy(policy) => client
-				.policy(policy)
-				.map(|_| SUCCESS_MESSAGE.into()),
+				Response::Diff([(a, a_value), (b, b_value)])
+			},
 
-			ClientCommand::Status(watch) => {
-				let status = client.status()?;
+			ClientCommand::MGet(keys) => {
+				let items = keys.into_iter().map(|key| (key, ()));
 
-				let mut title_output = "PaperCache status".to_string();
+				Response::Keys(multi::send(client, action, items.collect(), |client, key, _| {
+					client.get(key).map(Response::Bytes)
+				})?)
+			},
 
-				if watch {
-					title_output += " (watching)";
-				}
+			ClientCommand::MSet(pairs, ttl) => {
+				Response::Keys(multi::send(client, action, pairs, |client, key, value| {
+					client.set(key, value, ttl).map(|_| Response::Done)
+				})?)
+			},
 
-				let pid_output = format!("pid:\t\t{}", status.pid());
+			ClientCommand::MDel(keys) => {
+				let items = keys.into_iter().map(|key| (key, ()));
 
-				let max_size_output = format!(
-					"max_size:\t{} ({} B)",
-					fmt::memory(status.max_size(), Some(2)),
-					status.max_size(),
-				);
+				Response::Keys(multi::send(client, action, items.collect(), |client, key, _| {
+					client.del(key).map(|_| Response::Done)
+				})?)
+			},
 
-				let used_size_output = format!(
-					"used_size:\t{} ({} B)",
-					fmt::memory(status.used_size(), Some(2)),
-					status.used_size(),
-				);
+			ClientCommand::MHas(keys) => {
+				let items = keys.into_iter().map(|key| (key, ()));
 
-				let num_objects_output =
-					format!("num_objects:\t{}", fmt::number(status.num_objects()),);
+				Response::Keys(multi::send(client, action, items.collect(), |client, key, _| {
+					client.has(key).map(Response::Bool)
+				})?)
+			},
 
-				let rss_output = format!(
-					"rss:\t\t{} ({} B)",
-					fmt::memory(status.rss(), Some(2)),
-					status.rss(),
-				);
+			ClientCommand::Wipe => client.wipe().map(|_| Response::Done)?,
 
-				let hwm_output = format!(
-					"hwm:\t\t{} ({} B)",
-					fmt::memory(status.hwm(), Some(2)),
-					status.hwm(),
-				);
+			// the current size is needed to resolve a relative size, and to
+			// show how the size changed
+			ClientCommand::Resize(size) => {
+				let resize = Resize::new(&client.status()?, size)?;
+				client.resize(resize.new_size())?;
 
-				let total_gets_output =
-					format!("total_gets:\t{}", fmt::number(status.total_gets()),);
+				Response::Resize(resize)
+			},
 
-				let total_sets_output =
-					format!("total_sets:\t{}", fmt::number(status.total_sets()),);
+			ClientCommand::Policy(Some(policy)) => client
+				.policy(policy)
+				.map(|_| Response::Done)?,
 
-				let total_dels_output =
-					format!("total_dels:\t{}", fmt::number(status.total_dels()),);
+			ClientCommand::Policy(None) => Response::Policies(client.status()?),
 
-				let miss_ratio_output = format!("miss_ratio:\t{:.3}", status.miss_ratio(),);
+			ClientCommand::Status(_) => Response::Status(client.status()?),
+		};
 
-				let policies_str = status
-					.policies()
-					.iter()
-					.map(|policy| format!("* {policy}"))
-					.collect::<Vec<_>>()
-					.join("\n");
+		Ok(response)
+	}
+}
 
-				let policies_output = format!("policies:\n{policies_str}");
+/// The maximum number of characters of a value shown in a request.
+const MAX_PREVIEW_CHARS: usize = 32;
 
-				let policy_str = if status.is_auto_policy() {
-					format!("auto({})", status.policy())
-				} else {
-					status.policy().to_string()
-				};
+fn set_request(key: &str, value: &[u8], ttl: Option<u32>) -> String {
+	format!(
+		"set {key} {} ({} B){}",
+		value_preview(value),
+		value.len(),
+		set_ttl_str(ttl),
+	)
+}
 
-				let policy_output = format!("policy:\t\t{policy_str}");
+/// Returns the start of the value, quoted if it is text and escaped
+/// otherwise.
+fn value_preview(value: &[u8]) -> String {
+	let preview = match str::from_utf8(value) {
+		Ok(text) => format!("{text:?}"),
+		Err(_) => format!("b\"{}\"", value.escape_ascii()),
+	};
+
+	match preview.char_indices().nth(MAX_PREVIEW_CHARS) {
+		Some((index, _)) => format!("{}...", &preview[..index]),
+		None => preview,
+	}
+}
 
-				let uptime = format!("uptime:\t\t{}", fmt::timespan(status.uptime()),);
+fn ttl_str(ttl: Option<u32>) -> String {
+	match ttl {
+		Some(ttl) => format!("{ttl}s"),
+		None => "none".into(),
+	}
+}
 
-				let value = format!(
-					"{title_output}\n{pid_output}\n{max_size_output}\n{used_size_output}\n{num_objects_output}\n{rss_output}\n{hwm_output}\n{total_gets_output}\n{total_sets_output}\n{total_dels_output}\n{miss_ratio_output}\n{policies_output}\n{policy_output}\n{uptime}",
-				).into();
+fn set_ttl_str(ttl: Option<u32>) -> String {
+	match ttl {
+		Some(ttl) => format!(" ttl {ttl}s"),
+		None => String::new(),
+	}
+}
 
-				Ok(value)
-			},
+/// Groups the items by the node which owns each item's key, in the order
+/// the nodes are first seen.
+fn group<T: Clone>(
+	items: &[T],
+	key: impl Fn(&T) -> &str,
+	node: &mut impl FnMut(&str) -> Result<String, CommandError>,
+) -> Result<Vec<(String, Vec<T>)>, CommandError> {
+	let mut groups: Vec<(String, Vec<T>)> = Vec::new();
+
+	for item in items {
+		let item_node = node(key(item))?;
+
+		match groups.iter_mut().find(|(node, _)| *node == item_node) {
+			Some((_, group)) => group.push(item.clone()),
+			None => groups.push((item_node, vec![item.clone()])),
 		}
 	}
+
+	Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		command::{client::ClientCommand, error::CommandError},
+		shard::HashRing,
+	};
+
+	#[test]
+	fn it_peeks_diffed_keys_on_their_shards() {
+		let ring = HashRing::new(["a", "b"]);
+		let node = |key: &str| {
+			ring.node(key)
+				.map(ToOwned::to_owned)
+				.ok_or(CommandError::NoConnections)
+		};
+
+		// two keys which belong to different shards
+		let a = "key:0".to_owned();
+		let b = (1..100)
+			.map(|index| format!("key:{index}"))
+			.find(|key| ring.node(key) != ring.node(&a))
+			.unwrap();
+
+		let parts = ClientCommand::Diff(a.clone(), b.clone())
+			.partition(node)
+			.unwrap();
+
+		let peeks = parts
+			.iter()
+			.map(|(node, part)| match part {
+				ClientCommand::Peek(key, _) => (node.as_str(), key.as_str()),
+				_ => panic!("expected a peek command"),
+			})
+			.collect::<Vec<_>>();
+
+		assert_eq!(peeks, [
+			(ring.node(&a).unwrap(), a.as_str()),
+			(ring.node(&b).unwrap(), b.as_str()),
+		]);
+		assert_ne!(peeks[0].0, peeks[1].0);
+	}
 }
@@ -0,0 +1,248 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	fmt::{self, Display},
+	str::FromStr,
+};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use crate::command::error::CommandError;
+
+/// A fixed-width numeric type and byte order used to encode and decode
+/// values (e.g., `u64le` or `f64be`).
+#[derive(Clone, Copy, PartialEq)]
+pub struct Codec {
+	kind:  Kind,
+	order: Order,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+	U16,
+	U32,
+	U64,
+
+	I16,
+	I32,
+	I64,
+
+	F32,
+	F64,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Order {
+	Little,
+	Big,
+}
+
+/// A decoded value.
+#[derive(Clone, Copy)]
+pub enum Number {
+	Unsigned(u64),
+	Signed(i64),
+	Float(f64),
+}
+
+impl Codec {
+	/// Returns the number of bytes in an encoded value.
+	pub fn width(&self) -> usize {
+		match self.kind {
+			Kind::U16 | Kind::I16 => 2,
+			Kind::U32 | Kind::I32 | Kind::F32 => 4,
+			Kind::U64 | Kind::I64 | Kind::F64 => 8,
+		}
+	}
+
+	/// Encodes the number in the supplied string.
+	pub fn encode(&self, value: &str) -> Result<Vec<u8>, CommandError> {
+		let mut buf = vec![0; self.width()];
+
+		match self.order {
+			Order::Little => self.write::<LittleEndian>(&mut buf, value)?,
+			Order::Big => self.write::<BigEndian>(&mut buf, value)?,
+		}
+
+		Ok(buf)
+	}
+
+	/// Decodes the buffer, which must be exactly as long as the type.
+	pub fn decode(&self, buf: &[u8]) -> Result<Number, CommandError> {
+		if buf.len() != self.width() {
+			return Err(CommandError::InvalidValueLength(
+				self.to_string(),
+				self.width(),
+				buf.len(),
+			));
+		}
+
+		let number = match self.order {
+			Order::Little => self.read::<LittleEndian>(buf),
+			Order::Big => self.read::<BigEndian>(buf),
+		};
+
+		Ok(number)
+	}
+
+	fn write<B: ByteOrder>(&self, buf: &mut [u8], value: &str) -> Result<(), CommandError> {
+		fn parse<T: FromStr>(value: &str) -> Result<T, CommandError> {
+			value
+				.parse::<T>()
+				.map_err(|_| CommandError::InvalidTypedValue)
+		}
+
+		match self.kind {
+			Kind::U16 => B::write_u16(buf, parse(value)?),
+			Kind::U32 => B::write_u32(buf, parse(value)?),
+			Kind::U64 => B::write_u64(buf, parse(value)?),
+
+			Kind::I16 => B::write_i16(buf, parse(value)?),
+			Kind::I32 => B::write_i32(buf, parse(value)?),
+			Kind::I64 => B::write_i64(buf, parse(value)?),
+
+			Kind::F32 => B::write_f32(buf, parse(value)?),
+			Kind::F64 => B::write_f64(buf, parse(value)?),
+		}
+
+		Ok(())
+	}
+
+	fn read<B: ByteOrder>(&self, buf: &[u8]) -> Number {
+		match self.kind {
+			Kind::U16 => Number::Unsigned(B::read_u16(buf).into()),
+			Kind::U32 => Number::Unsigned(B::read_u32(buf).into()),
+			Kind::U64 => Number::Unsigned(B::read_u64(buf)),
+
+			Kind::I16 => Number::Signed(B::read_i16(buf).into()),
+			Kind::I32 => Number::Signed(B::read_i32(buf).into()),
+			Kind::I64 => Number::Signed(B::read_i64(buf)),
+
+			Kind::F32 => Number::Float(B::read_f32(buf).into()),
+			Kind::F64 => Number::Float(B::read_f64(buf)),
+		}
+	}
+}
+
+impl FromStr for Codec {
+	type Err = CommandError;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		let (kind, order) = if let Some(kind) = value.strip_suffix("le") {
+			(kind, Order::Little)
+		} else if let Some(kind) = value.strip_suffix("be") {
+			(kind, Order::Big)
+		} else {
+			return Err(CommandError::InvalidCodec);
+		};
+
+		let kind = match kind {
+			"u16" => Kind::U16,
+			"u32" => Kind::U32,
+			"u64" => Kind::U64,
+
+			"i16" => Kind::I16,
+			"i32" => Kind::I32,
+			"i64" => Kind::I64,
+
+			"f32" => Kind::F32,
+			"f64" => Kind::F64,
+
+			_ => return Err(CommandError::InvalidCodec),
+		};
+
+		Ok(Codec {
+			kind,
+			order,
+		})
+	}
+}
+
+impl Display for Codec {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let kind = match self.kind {
+			Kind::U16 => "u16",
+			Kind::U32 => "u32",
+			Kind::U64 => "u64",
+
+			Kind::I16 => "i16",
+			Kind::I32 => "i32",
+			Kind::I64 => "i64",
+
+			Kind::F32 => "f32",
+			Kind::F64 => "f64",
+		};
+
+		let order = match self.order {
+			Order::Little => "le",
+			Order::Big => "be",
+		};
+
+		write!(f, "{kind}{order}")
+	}
+}
+
+impl Display for Number {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Number::Unsigned(value) => write!(f, "{value}"),
+			Number::Signed(value) => write!(f, "{value}"),
+			Number::Float(value) => write!(f, "{value}"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+
+	use crate::command::{codec::Codec, error::CommandError};
+
+	#[test]
+	fn it_parses_codecs() {
+		for codec in ["u16le", "u32be", "u64le", "i16be", "i32le", "i64be", "f32le", "f64be"] {
+			let parsed = Codec::from_str(codec).map(|codec| codec.to_string());
+			assert_eq!(parsed, Ok(codec.to_owned()));
+		}
+	}
+
+	#[test]
+	fn it_rejects_invalid_codecs() {
+		for codec in ["", "le", "u64", "u8le", "u64LE", "u64lebe", "x"] {
+			assert!(Codec::from_str(codec) == Err(CommandError::InvalidCodec));
+		}
+	}
+
+	#[test]
+	fn it_rejects_non_ascii_codecs() {
+		for codec in ["日本", "éa", "u64é", "u6éle", "u64lé"] {
+			assert!(Codec::from_str(codec) == Err(CommandError::InvalidCodec));
+		}
+	}
+
+	#[test]
+	fn it_encodes_and_decodes() {
+		let codec = Codec::from_str("u32be").unwrap();
+		let encoded = codec.encode("258").unwrap();
+
+		assert_eq!(encoded, [0, 0, 1, 2]);
+		assert_eq!(codec.decode(&encoded).unwrap().to_string(), "258");
+
+		let codec = Codec::from_str("i16le").unwrap();
+		assert_eq!(codec.encode("-2").unwrap(), [0xfe, 0xff]);
+	}
+
+	#[test]
+	fn it_rejects_invalid_values() {
+		let codec = Codec::from_str("u16le").unwrap();
+
+		assert!(codec.encode("65536") == Err(CommandError::InvalidTypedValue));
+		assert!(codec.encode("日本") == Err(CommandError::InvalidTypedValue));
+		assert!(matches!(codec.decode(&[0; 3]), Err(CommandError::InvalidValueLength(..))));
+	}
+}
@@ -0,0 +1,80 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	fmt::{self, Display},
+	str::FromStr,
+};
+
+use fasthash::xx;
+use sha2::{Digest as _, Sha256};
+
+use crate::command::error::CommandError;
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Algorithm {
+	#[default]
+	Xxh64,
+
+	Sha256,
+}
+
+/// The length and hash of a value.
+pub struct Digest {
+	size:      usize,
+	algorithm: Algorithm,
+	hash:      Vec<u8>,
+}
+
+impl Digest {
+	pub fn new(buf: &[u8], algorithm: Algorithm) -> Self {
+		let hash = match algorithm {
+			Algorithm::Xxh64 => xx::hash64(buf).to_be_bytes().to_vec(),
+			Algorithm::Sha256 => Sha256::digest(buf).to_vec(),
+		};
+
+		Digest {
+			size: buf.len(),
+			algorithm,
+			hash,
+		}
+	}
+
+	pub fn size(&self) -> usize {
+		self.size
+	}
+
+	pub fn algorithm(&self) -> Algorithm {
+		self.algorithm
+	}
+
+	pub fn hash(&self) -> &[u8] {
+		&self.hash
+	}
+}
+
+impl FromStr for Algorithm {
+	type Err = CommandError;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		match value.to_lowercase().as_str() {
+			"xxh64" | "xx" => Ok(Algorithm::Xxh64),
+			"sha256" | "sha-256" => Ok(Algorithm::Sha256),
+
+			_ => Err(CommandError::InvalidAlgorithm),
+		}
+	}
+}
+
+impl Display for Algorithm {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Algorithm::Xxh64 => write!(f, "xxh64"),
+			Algorithm::Sha256 => write!(f, "sha256"),
+		}
+	}
+}
@@ -5,6 +5,7 @@
  * LICENSE file in the root directory of this source tree.
  */
 
+use paper_client::PaperClientError;
 use thiserror::Error;
 
 #[derive(Debug, PartialEq, Error)]
@@ -21,21 +22,161 @@ pub enum CommandError {
 	#[error("invalid cache size")]
 	InvalidCacheSize,
 
+	#[error("cache size must be greater than zero")]
+	CacheSizeTooSmall,
+
 	#[error("invalid TTL")]
 	InvalidTtl,
 
+	#[error("TTL must be at most 4294967295 seconds (about 136 years)")]
+	TtlTooLarge,
+
+	#[error("invalid expiry time")]
+	InvalidExpiry,
+
+	#[error("expiry time is in the past")]
+	ExpiryInPast,
+
 	#[error("invalid policy")]
 	InvalidPolicy,
 
-	#[error("could not display response data")]
-	InvalidResponse,
+	#[error("invalid address: {0}")]
+	InvalidAddress(&'static str),
+
+	#[error("invalid port (must be between 1 and 65535)")]
+	InvalidPort,
+
+	#[error("could not read profiles file")]
+	InvalidProfiles,
+
+	#[error("profile not found")]
+	UnknownProfile,
+
+	#[error("could not open audit log")]
+	InvalidAuditLog,
+
+	#[error("could not write to audit log")]
+	AuditLogFailed,
+
+	#[error("could not read recording")]
+	InvalidRecording,
+
+	#[error("could not write to recording")]
+	RecordingFailed,
+
+	#[error("session is already being recorded")]
+	AlreadyRecording,
+
+	#[error("session is not being recorded")]
+	NotRecording,
+
+	#[error("could not read value file")]
+	InvalidValueFile,
+
+	#[error("could not write output file")]
+	InvalidOutputFile,
+
+	#[error("values can only be read from stdin when it is not a terminal")]
+	StdinIsTerminal,
+
+	#[error("unknown type (expected e.g., u64le, i32be, f64le)")]
+	InvalidCodec,
+
+	#[error("value does not match its type")]
+	InvalidTypedValue,
+
+	#[error("value is {2} B but <{0}> requires {1} B")]
+	InvalidValueLength(String, usize, usize),
+
+	#[error("invalid JSON path (expected e.g., .profile.name or .items[0])")]
+	InvalidJsonPath,
+
+	#[error("value is not valid JSON")]
+	InvalidJson,
+
+	#[error("path not found in value")]
+	UnknownJsonPath,
+
+	#[error("invalid hash algorithm (expected xxh64 or sha256)")]
+	InvalidAlgorithm,
+
+	#[error("cannot edit a binary value")]
+	BinaryEdit,
+
+	#[error("could not write temporary file")]
+	InvalidTempFile,
+
+	#[error("editor exited with an error")]
+	EditorFailed,
 
 	#[error("disconnected")]
 	Disconnected,
 
+	#[error("not connected to a server")]
+	NotConnected,
+
+	#[error("connection not found")]
+	UnknownConnection,
+
+	#[error("cannot close the only open connection")]
+	LastConnection,
+
+	#[error("only cache commands can be sent to another connection")]
+	InvalidTarget,
+
+	#[error("could not read hosts file")]
+	InvalidHostsFile,
+
+	#[error("could not connect to any host")]
+	NoConnections,
+
+	#[error("not in sharded mode")]
+	NotSharded,
+
+	#[error("cannot change the hosts of a fan-out or sharded session")]
+	FixedHosts,
+
+	#[error("keys belong to different shards")]
+	CrossShardKeys,
+
+	#[error("shard {0} failed: {1}")]
+	ShardFailed(String, String),
+
+	#[error("cannot write the values of several hosts to one file")]
+	GroupOutputFile,
+
+	#[error("cannot edit the values of several hosts at once")]
+	GroupEdit,
+
+	#[error("cannot expand commands which are sent to several hosts")]
+	GroupExpansion,
+
+	#[error("cannot expand the arguments of this command")]
+	InvalidExpansion,
+
+	#[error("expanded arguments must have the same number of words")]
+	MismatchedExpansion,
+
+	#[error("expansion has too many words")]
+	ExpansionTooLarge,
+
+	#[error("cannot run commands which change the cache in read-only mode")]
+	ReadOnly,
+
+	#[error("only commands which are sent to the server can be dry-run")]
+	InvalidDryRun,
+
+	/// The command failed with the supplied error, which was already
+	/// reported.
+	#[error("{0}")]
+	CommandFailed(String),
+
 	#[error("closing connection")]
 	Interrupted,
 
+	#[error(transparent)]
+	Client(#[from] PaperClientError),
+
 	#[error("internal error")]
 	Internal,
 }
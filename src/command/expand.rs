@@ -0,0 +1,265 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use serde_json::Value;
+
+use crate::command::error::CommandError;
+
+/// The maximum number of words one argument can be expanded into.
+const MAX_WORDS: usize = 100_000;
+
+/// Expands the braces in the word the way a shell does. A list expands into
+/// each of its items (e.g., `tmp:{a,b}` into `tmp:a tmp:b`), a range into
+/// each of its numbers or letters (e.g., `user:{1..3}` into `user:1 user:2
+/// user:3`, or `{01..10..3}` into `01 04 07 10`) and several braces into
+/// every combination of them. Braces which do not contain a list or range
+/// are left as they are, as are words which are valid JSON (e.g., a value
+/// such as `{"a":1,"b":2}`).
+pub fn expand(word: &str) -> Result<Vec<String>, CommandError> {
+	if serde_json::from_str::<Value>(word).is_ok() {
+		return Ok(vec![word.to_owned()]);
+	}
+
+	expand_word(word)
+}
+
+fn expand_word(word: &str) -> Result<Vec<String>, CommandError> {
+	let mut search = 0;
+
+	while let Some(offset) = word[search..].find('{') {
+		let start = search + offset;
+
+		let Some(end) = closing_brace(word, start) else {
+			break;
+		};
+
+		if let Some(items) = items(&word[start + 1..end])? {
+			let prefix = &word[..start];
+			let suffixes = expand_word(&word[end + 1..])?;
+
+			let mut words = Vec::new();
+
+			for item in items {
+				for item_word in expand_word(&item)? {
+					for suffix in &suffixes {
+						words.push(format!("{prefix}{item_word}{suffix}"));
+					}
+
+					if words.len() > MAX_WORDS {
+						return Err(CommandError::ExpansionTooLarge);
+					}
+				}
+			}
+
+			return Ok(words);
+		}
+
+		search = start + 1;
+	}
+
+	Ok(vec![word.to_owned()])
+}
+
+/// Returns the index of the brace which closes the one at the supplied
+/// index, skipping any nested braces.
+fn closing_brace(word: &str, start: usize) -> Option<usize> {
+	let mut depth = 0;
+
+	for (index, c) in word[start..].char_indices() {
+		match c {
+			'{' => depth += 1,
+			'}' if depth == 1 => return Some(start + index),
+			'}' => depth -= 1,
+			_ => {},
+		}
+	}
+
+	None
+}
+
+/// Returns the items of the list or range between a pair of braces, or
+/// `None` if it is neither.
+fn items(body: &str) -> Result<Option<Vec<String>>, CommandError> {
+	let list = split_list(body);
+
+	if list.len() > 1 {
+		return Ok(Some(list));
+	}
+
+	let bounds = body.split("..").collect::<Vec<_>>();
+
+	match bounds.as_slice() {
+		[start, end] => range(start, end, "1"),
+		[start, end, step] => range(start, end, step),
+		_ => Ok(None),
+	}
+}
+
+/// Splits the text at each comma which is not inside nested braces.
+fn split_list(body: &str) -> Vec<String> {
+	let mut items = Vec::new();
+	let mut item = String::new();
+	let mut depth = 0;
+
+	for c in body.chars() {
+		match c {
+			',' if depth == 0 => {
+				items.push(item);
+				item = String::new();
+				continue;
+			},
+
+			'{' => depth += 1,
+			'}' => depth -= 1,
+			_ => {},
+		}
+
+		item.push(c);
+	}
+
+	items.push(item);
+	items
+}
+
+fn range(start: &str, end: &str, step: &str) -> Result<Option<Vec<String>>, CommandError> {
+	let Ok(step) = step.parse::<i64>() else {
+		return Ok(None);
+	};
+
+	let step = match step.unsigned_abs() {
+		0 => 1,
+		step => step,
+	};
+
+	if let (Ok(start_number), Ok(end_number)) = (start.parse::<i64>(), end.parse::<i64>()) {
+		// numbers are padded with zeros if either bound is (e.g., `{01..10}`)
+		let width = match is_zero_padded(start) || is_zero_padded(end) {
+			true => start.len().max(end.len()),
+			false => 0,
+		};
+
+		let words = steps(start_number, end_number, step)?
+			.into_iter()
+			.map(|number| format!("{number:0width$}"))
+			.collect();
+
+		return Ok(Some(words));
+	}
+
+	let (Some(start_char), Some(end_char)) = (single_letter(start), single_letter(end)) else {
+		return Ok(None);
+	};
+
+	let words = steps(start_char as i64, end_char as i64, step)?
+		.into_iter()
+		.filter_map(|code| char::from_u32(code as u32))
+		.map(String::from)
+		.collect();
+
+	Ok(Some(words))
+}
+
+/// Returns every number from the start to the end (inclusive) in
+/// increments of the step, counting down if the end is before the start.
+fn steps(start: i64, end: i64, step: u64) -> Result<Vec<i64>, CommandError> {
+	let count = (start.abs_diff(end) / step)
+		.checked_add(1)
+		.filter(|count| *count <= MAX_WORDS as u64)
+		.ok_or(CommandError::ExpansionTooLarge)?;
+
+	// every number is between the start and the end, but the step itself
+	// may not fit in an `i64`
+	let step = match start <= end {
+		true => i128::from(step),
+		false => -i128::from(step),
+	};
+
+	let numbers = (0..count)
+		.map(|index| (i128::from(start) + i128::from(index) * step) as i64)
+		.collect();
+
+	Ok(numbers)
+}
+
+fn is_zero_padded(bound: &str) -> bool {
+	let digits = bound.trim_start_matches('-');
+	digits.len() > 1 && digits.starts_with('0')
+}
+
+fn single_letter(bound: &str) -> Option<char> {
+	let mut chars = bound.chars();
+
+	match (chars.next(), chars.next()) {
+		(Some(c), None) if c.is_ascii_alphabetic() => Some(c),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::command::{error::CommandError, expand::expand};
+
+	fn words(words: &[&str]) -> Result<Vec<String>, CommandError> {
+		Ok(words.iter().map(|word| word.to_string()).collect())
+	}
+
+	#[test]
+	fn it_expands_lists() {
+		assert_eq!(expand("tmp:{a,b}"), words(&["tmp:a", "tmp:b"]));
+		assert_eq!(expand("{a,}x"), words(&["ax", "x"]));
+		assert_eq!(expand("{a,{b,c}}"), words(&["a", "b", "c"]));
+	}
+
+	#[test]
+	fn it_expands_ranges() {
+		assert_eq!(expand("user:{1..3}"), words(&["user:1", "user:2", "user:3"]));
+		assert_eq!(expand("{3..1}"), words(&["3", "2", "1"]));
+		assert_eq!(expand("{-1..1}"), words(&["-1", "0", "1"]));
+		assert_eq!(expand("{01..10..3}"), words(&["01", "04", "07", "10"]));
+		assert_eq!(expand("{1..6..-2}"), words(&["1", "3", "5"]));
+		assert_eq!(expand("{a..e..2}"), words(&["a", "c", "e"]));
+	}
+
+	#[test]
+	fn it_expands_combinations() {
+		assert_eq!(expand("{a,b}{1..2}"), words(&["a1", "a2", "b1", "b2"]));
+	}
+
+	#[test]
+	fn it_leaves_other_words() {
+		assert_eq!(expand("plain"), words(&["plain"]));
+		assert_eq!(expand("{a}"), words(&["{a}"]));
+		assert_eq!(expand("{a..bc}"), words(&["{a..bc}"]));
+		assert_eq!(expand("{unclosed"), words(&["{unclosed"]));
+		assert_eq!(expand(r#"{"a":1,"b":2}"#), words(&[r#"{"a":1,"b":2}"#]));
+		assert_eq!(expand("日本{é,ü}"), words(&["日本é", "日本ü"]));
+	}
+
+	#[test]
+	fn it_rejects_large_expansions() {
+		assert_eq!(expand("{1..100001}"), Err(CommandError::ExpansionTooLarge));
+		assert_eq!(expand("{1..1000}{1..1000}"), Err(CommandError::ExpansionTooLarge));
+
+		assert_eq!(
+			expand("{-9223372036854775808..9223372036854775807}"),
+			Err(CommandError::ExpansionTooLarge),
+		);
+	}
+
+	#[test]
+	fn it_expands_ranges_at_the_limits() {
+		assert_eq!(
+			expand("{-9223372036854775808..9223372036854775807..9223372036854775807}"),
+			words(&["-9223372036854775808", "-1", "9223372036854775806"]),
+		);
+
+		assert_eq!(
+			expand("{9223372036854775807..-9223372036854775808..-9223372036854775808}"),
+			words(&["9223372036854775807", "-1"]),
+		);
+	}
+}
@@ -0,0 +1,132 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use paper_client::{
+	PaperClient,
+	PaperClientError,
+	error::{PaperCacheError, PaperClientResult},
+};
+
+use crate::{
+	command::{client::Response, error::CommandError},
+	connection,
+};
+
+/// The result of a command for one of several keys.
+pub struct KeyResult {
+	key:    String,
+	result: Result<Response, CommandError>,
+}
+
+/// The results of a multi-key command, in the order the keys were given.
+pub struct KeyResults {
+	/// Describes a successful result (e.g., "found" or "deleted").
+	action:  &'static str,
+	results: Vec<KeyResult>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Outcome {
+	Ok,
+	Missing,
+	Error,
+}
+
+impl KeyResult {
+	pub fn new(key: String, result: Result<Response, CommandError>) -> Self {
+		KeyResult {
+			key,
+			result,
+		}
+	}
+
+	pub fn key(&self) -> &str {
+		&self.key
+	}
+
+	pub fn result(&self) -> Result<&Response, &CommandError> {
+		self.result.as_ref()
+	}
+
+	pub fn outcome(&self) -> Outcome {
+		match &self.result {
+			Ok(Response::Bool(false)) => Outcome::Missing,
+			Ok(_) => Outcome::Ok,
+
+			Err(CommandError::Client(PaperClientError::CacheError(PaperCacheError::KeyNotFound))) => {
+				Outcome::Missing
+			},
+
+			Err(_) => Outcome::Error,
+		}
+	}
+}
+
+impl KeyResults {
+	pub fn new(action: &'static str) -> Self {
+		KeyResults {
+			action,
+			results: Vec::new(),
+		}
+	}
+
+	pub fn action(&self) -> &'static str {
+		self.action
+	}
+
+	pub fn results(&self) -> &[KeyResult] {
+		&self.results
+	}
+
+	pub fn push(&mut self, result: KeyResult) {
+		self.results.push(result);
+	}
+
+	/// Adds the results of another part of the same command, which was sent
+	/// to a different connection.
+	pub fn extend(&mut self, other: KeyResults) {
+		self.results.extend(other.results);
+	}
+
+	/// Sorts the results to match the order of the supplied keys.
+	pub fn sort(&mut self, keys: &[&str]) {
+		self.results.sort_by_key(|result| {
+			keys.iter()
+				.position(|key| *key == result.key)
+				.unwrap_or(keys.len())
+		});
+	}
+
+	pub fn count(&self, outcome: Outcome) -> usize {
+		self.results
+			.iter()
+			.filter(|result| result.outcome() == outcome)
+			.count()
+	}
+}
+
+/// Sends a command for each key. An error for one key does not prevent the
+/// command from being sent for the others, unless the connection is lost.
+pub fn send<T>(
+	client: &mut PaperClient,
+	action: &'static str,
+	items: Vec<(String, T)>,
+	mut send_one: impl FnMut(&mut PaperClient, &str, T) -> PaperClientResult<Response>,
+) -> PaperClientResult<KeyResults> {
+	let mut results = KeyResults::new(action);
+
+	for (key, item) in items {
+		let result = match send_one(client, &key, item) {
+			Err(err) if connection::is_disconnect(&err) => return Err(err),
+			result => result.map_err(CommandError::from),
+		};
+
+		results.push(KeyResult::new(key, result));
+	}
+
+	Ok(results)
+}
@@ -5,14 +5,29 @@
  * LICENSE file in the root directory of this source tree.
  */
 
-use std::str::FromStr;
+use std::{
+	fs,
+	io::{self, IsTerminal, Read},
+	str::FromStr,
+};
 
 use paper_client::PaperPolicy;
-use parse_size::parse_size as parse_input_size;
 use regex::Regex;
 
 use crate::{
-	command::{CliCommand, ClientCommand, Command, error::CommandError},
+	addr::ServerAddr,
+	command::{
+		CliCommand,
+		ClientCommand,
+		Command,
+		codec::Codec,
+		digest::Algorithm,
+		error::CommandError,
+		expand::expand,
+		resize::CacheSize,
+		ttl,
+		view::View,
+	},
 	line_reader::{LineReader, LineReaderError},
 };
 
@@ -22,35 +37,59 @@ pub struct CommandParser {
 	tokenizer:     Regex,
 	escaped_quote: Regex,
 
-	reading: bool,
+	reading:   bool,
+	read_only: bool,
+	dry_run:   bool,
 }
 
 impl CommandParser {
-	pub fn new(host: &str, port: u32) -> Self {
-		let prompt = format!("\x1B[32m{host}:{port:0>4}\x1B[0m> ");
-		let mut line_reader = LineReader::new(prompt);
+	pub fn new(addr: &ServerAddr) -> Self {
+		let mut line_reader = LineReader::new(connected_prompt(addr));
 
 		line_reader.register_hint("ping");
 		line_reader.register_hint("version");
 
 		line_reader.register_hint("auth <token>");
 
-		line_reader.register_hint("get <key>");
-		line_reader.register_hint("set <key> <value> [ttl]");
+		line_reader.register_hint("get <key> [.path] [--json] [--compact] [--hex|--base64|as <type>] [--out <path>]");
+		line_reader.register_hint("set <key> <value|type:value|@path|-> [ttl]");
 		line_reader.register_hint("del <key>");
 
 		line_reader.register_hint("has <key>");
-		line_reader.register_hint("peek <key>");
+		line_reader.register_hint("peek <key> [.path] [--json] [--compact] [--hex|--base64|as <type>] [--out <path>]");
 		line_reader.register_hint("ttl <key> [ttl]");
 		line_reader.register_hint("size <key>");
 
+		line_reader.register_hint("mget <key>...");
+		line_reader.register_hint("mset <key> <value>... [ttl]");
+		line_reader.register_hint("mdel <key>...");
+		line_reader.register_hint("mhas <key>...");
+
+		line_reader.register_hint("edit <key> [ttl]");
+		line_reader.register_hint("digest <key> [xxh64|sha256]");
+		line_reader.register_hint("diff <key> <key>");
+
 		line_reader.register_hint("wipe");
 
-		line_reader.register_hint("resize <size>");
-		line_reader.register_hint("policy <policy>");
+		line_reader.register_hint("resize <size|+size|-size|percent%|factor x>");
+		line_reader.register_hint("policy [policy]");
 
 		line_reader.register_hint("status [watch]");
 
+		line_reader.register_hint("dry <command>");
+
+		line_reader.register_hint("connect <[paper://]host[:port]|profile>");
+		line_reader.register_hint("disconnect");
+		line_reader.register_hint("reconnect");
+
+		line_reader.register_hint("open <name> <[paper://]host[:port]|profile>");
+		line_reader.register_hint("use <name>");
+		line_reader.register_hint("close <name>");
+		line_reader.register_hint("connections");
+		line_reader.register_hint("shard <key>");
+
+		line_reader.register_hint("record <start <path>|stop>");
+
 		line_reader.register_hint("help");
 		line_reader.register_hint("clear");
 		line_reader.register_hint("quit");
@@ -63,9 +102,48 @@ impl CommandParser {
 			escaped_quote: Regex::new(r#"\\""#).unwrap(),
 
 			reading: true,
+			read_only: false,
+			dry_run: false,
 		}
 	}
 
+	/// Updates the prompt to show the supplied connection name (if any) and
+	/// address, or that the connection is closed if no address is supplied.
+	pub fn set_target(&mut self, name: Option<&str>, addr: Option<&ServerAddr>) {
+		let name_prompt = match name {
+			Some(name) => format!("\x1B[36m[{name}]\x1B[0m "),
+			None => String::new(),
+		};
+
+		let addr_prompt = match addr {
+			Some(addr) => connected_prompt(addr),
+			None => "\x1B[31m(disconnected)\x1B[0m> ".into(),
+		};
+
+		self.set_prompt(name_prompt + &addr_prompt);
+	}
+
+	/// Updates the prompt to show that commands are sent to a group of hosts.
+	pub fn set_group(&mut self, label: &str, num_hosts: usize) {
+		self.set_prompt(format!("\x1B[32m{label} ({num_hosts} hosts)\x1B[0m> "));
+	}
+
+	/// Rejects commands which change the cache from now on. The prompt is
+	/// marked as read-only once it is next updated.
+	pub fn set_read_only(&mut self) {
+		self.read_only = true;
+	}
+
+	pub fn is_read_only(&self) -> bool {
+		self.read_only
+	}
+
+	/// Parses every command which would be sent to the server as a dry run
+	/// from now on. The prompt is marked once it is next updated.
+	pub fn set_dry_run(&mut self) {
+		self.dry_run = true;
+	}
+
 	pub fn reading(&self) -> bool {
 		self.reading
 	}
@@ -74,19 +152,42 @@ impl CommandParser {
 		self.reading = false;
 	}
 
-	pub fn read(&mut self) -> Result<Command, CommandError> {
-		let tokens = match self.line_reader.read() {
-			Ok(line) => self.parse_line(&line)?,
+	pub fn read_line(&mut self) -> Result<String, CommandError> {
+		match self.line_reader.read() {
+			Ok(line) => Ok(line),
 
 			Err(LineReaderError::Closed) => {
 				self.reading = false;
-				return Err(CommandError::Interrupted);
+				Err(CommandError::Interrupted)
 			},
 
-			Err(_) => return Err(CommandError::InvalidCommand),
-		};
+			Err(_) => Err(CommandError::InvalidCommand),
+		}
+	}
 
-		let command = parse_command(&tokens)?;
+	/// Parses a line which was read from the line reader, or supplied in
+	/// another way (e.g., with `--command`).
+	pub fn parse(&self, line: &str) -> Result<Command, CommandError> {
+		if let Some(line) = strip_dry_run(line) {
+			if line.trim().is_empty() {
+				return Err(CommandError::InvalidArguments("dry"));
+			}
+
+			return dry_run(parse_words(self.parse_line(line)?)?);
+		}
+
+		let words = self.parse_line(line)?;
+		let command = parse_words(words)?;
+
+		// dry runs do not change the cache, so they are allowed in
+		// read-only mode
+		if self.dry_run && !matches!(command, Command::Cli(_)) {
+			return dry_run(command);
+		}
+
+		if self.read_only && is_mutating(&command) {
+			return Err(CommandError::ReadOnly);
+		}
 
 		Ok(command)
 	}
@@ -99,8 +200,25 @@ impl CommandParser {
 		}
 	}
 
-	fn parse_line(&self, line: &str) -> Result<Vec<String>, CommandError> {
-		let mut tokens: Vec<String> = Vec::new();
+	fn set_prompt(&mut self, prompt: String) {
+		let read_only_prompt = match self.read_only {
+			true => "\x1B[31m[read-only]\x1B[0m ",
+			false => "",
+		};
+
+		let dry_run_prompt = match self.dry_run {
+			true => "\x1B[33m[dry-run]\x1B[0m ",
+			false => "",
+		};
+
+		self.line_reader
+			.set_prompt(format!("{read_only_prompt}{dry_run_prompt}{prompt}"));
+	}
+
+	/// Splits the line into tokens and expands the braces in each unquoted
+	/// key, returning the words of each token and whether it is quoted.
+	fn parse_line(&self, line: &str) -> Result<Vec<(Vec<String>, bool)>, CommandError> {
+		let mut tokens: Vec<(String, bool)> = Vec::new();
 
 		for capture in self.tokenizer.captures_iter(line) {
 			if let Some(token) = capture.get(0) {
@@ -117,7 +235,9 @@ impl CommandParser {
 					.replace_all(token, "\"")
 					.to_string();
 
-				tokens.push(token.to_string());
+				let is_quoted = capture.get(3).is_none();
+
+				tokens.push((token.to_string(), is_quoted));
 			}
 		}
 
@@ -125,13 +245,180 @@ impl CommandParser {
 			return Err(CommandError::EmptyCommand);
 		}
 
-		tokens[0].make_ascii_lowercase();
+		// the command follows the connection name in a targeted command
+		let command_index = match tokens[0].0.starts_with('@') {
+			true => 1,
+			false => 0,
+		};
+
+		if let Some((command, _)) = tokens.get_mut(command_index) {
+			command.make_ascii_lowercase();
+		}
+
+		let name = tokens
+			.get(command_index)
+			.map(|(command, _)| command.clone())
+			.unwrap_or_default();
+
+		let num_args = tokens.len().saturating_sub(command_index + 1);
+
+		tokens
+			.into_iter()
+			.enumerate()
+			.map(|(index, (token, is_quoted))| {
+				let is_key = index > command_index
+					&& is_key_argument(&name, index - command_index, num_args);
+
+				match is_key && !is_quoted {
+					true => Ok((expand(&token)?, is_quoted)),
+					false => Ok((vec![token], is_quoted)),
+				}
+			})
+			.collect()
+	}
+}
+
+fn connected_prompt(addr: &ServerAddr) -> String {
+	format!(
+		"\x1B[32m{}:{:0>4}\x1B[0m> ",
+		addr.host_literal(),
+		addr.port(),
+	)
+}
+
+fn is_mutating(command: &Command) -> bool {
+	match command {
+		Command::Client(command) | Command::Targeted(_, command) => command.is_mutating(),
+		Command::Expanded(_, commands) => commands.iter().any(is_mutating),
+		Command::Cli(_) | Command::Dry(_) => false,
+	}
+}
+
+/// Returns the rest of the line if it starts with the `dry` prefix.
+fn strip_dry_run(line: &str) -> Option<&str> {
+	let line = line.trim_start();
+
+	let (word, rest) = line
+		.split_once(char::is_whitespace)
+		.unwrap_or((line, ""));
+
+	word.eq_ignore_ascii_case("dry").then_some(rest)
+}
+
+fn dry_run(command: Command) -> Result<Command, CommandError> {
+	match command {
+		Command::Cli(_) => Err(CommandError::InvalidDryRun),
+		command => Ok(Command::Dry(Box::new(command))),
+	}
+}
+
+/// Returns `true` if the (one-indexed) argument of the command is a key,
+/// which is the only kind of argument that is expanded. Values, TTLs and
+/// the other arguments are always used as they are.
+fn is_key_argument(command: &str, index: usize, num_args: usize) -> bool {
+	match command {
+		"get" | "set" | "del" | "has" | "peek" | "ttl" | "size" | "edit" | "digest" => index == 1,
+		"diff" => index <= 2,
+		"mget" | "mdel" | "mhas" => true,
+
+		// the keys and values alternate, and an odd number of arguments ends
+		// with a TTL
+		"mset" => index % 2 == 1 && !(num_args % 2 == 1 && index == num_args),
+
+		_ => false,
+	}
+}
+
+/// Parses the words of each token into a command. If a key was expanded
+/// into several words, a single-key command which accepts only a key
+/// becomes the equivalent multi-key command (e.g., `del tmp:{a,b}` becomes
+/// `mdel tmp:a tmp:b`), the words are added to the keys of a multi-key
+/// command, and any other command is repeated once for each word.
+fn parse_words(tokens: Vec<(Vec<String>, bool)>) -> Result<Command, CommandError> {
+	let (words, quoted): (Vec<Vec<String>>, Vec<bool>) = tokens.into_iter().unzip();
+
+	if words.iter().all(|word| word.len() == 1) {
+		let tokens = words.into_iter().flatten().collect::<Vec<_>>();
+		return parse_command(&tokens, &quoted);
+	}
+
+	let command_index = match words[0][0].starts_with('@') {
+		true => 1,
+		false => 0,
+	};
+
+	let name = words
+		.get(command_index)
+		.map(|word| word[0].as_str())
+		.ok_or(CommandError::InvalidExpansion)?;
+
+	let multi_name = match (name, words.len() - command_index) {
+		("get", 2) => Some("mget"),
+		("del", 2) => Some("mdel"),
+		("has", 2) => Some("mhas"),
+		("mget" | "mdel" | "mhas", _) => Some(name),
+		_ => None,
+	};
+
+	if let Some(multi_name) = multi_name {
+		let mut tokens = words[..command_index]
+			.iter()
+			.flatten()
+			.cloned()
+			.collect::<Vec<_>>();
+
+		tokens.push(multi_name.to_owned());
+		tokens.extend(words[command_index + 1..].iter().flatten().cloned());
+
+		let num_keys = tokens.len() - command_index - 1;
 
-		Ok(tokens)
+		// the keys of a multi-key command are never read as values
+		let quoted = vec![false; tokens.len()];
+
+		return Ok(Command::Expanded(num_keys, vec![parse_command(&tokens, &quoted)?]));
+	}
+
+	let num_commands = words
+		.iter()
+		.map(Vec::len)
+		.max()
+		.unwrap_or(1);
+
+	if words.iter().any(|word| word.len() != 1 && word.len() != num_commands) {
+		return Err(CommandError::MismatchedExpansion);
 	}
+
+	let commands = (0..num_commands)
+		.map(|index| {
+			let tokens = words
+				.iter()
+				.map(|word| word[index.min(word.len() - 1)].clone())
+				.collect::<Vec<_>>();
+
+			match parse_command(&tokens, &quoted)? {
+				Command::Client(command) | Command::Targeted(_, command)
+					if command.keys().is_empty() || matches!(command, ClientCommand::Edit(..)) =>
+				{
+					Err(CommandError::InvalidExpansion)
+				},
+
+				command @ (Command::Client(_) | Command::Targeted(..)) => Ok(command),
+
+				_ => Err(CommandError::InvalidExpansion),
+			}
+		})
+		.collect::<Result<Vec<_>, CommandError>>()?;
+
+	Ok(Command::Expanded(num_commands, commands))
 }
 
-fn parse_command(tokens: &[String]) -> Result<Command, CommandError> {
+/// Parses the tokens of a command, each of which is either quoted or not.
+/// A quoted value is always used as it is.
+fn parse_command(tokens: &[String], quoted: &[bool]) -> Result<Command, CommandError> {
+	if let Some(name) = tokens[0].strip_prefix('@') {
+		return parse_targeted(name, &tokens[1..], &quoted[1..]);
+	}
+
 	match tokens[0].as_str() {
 		"ping" => parse_ping(tokens),
 		"version" => parse_version(tokens),
@@ -139,7 +426,7 @@ fn parse_command(tokens: &[String]) -> Result<Command, CommandError> {
 		"auth" => parse_auth(tokens),
 
 		"get" => parse_get(tokens),
-		"set" => parse_set(tokens),
+		"set" => parse_set(tokens, quoted),
 		"del" => parse_del(tokens),
 
 		"has" => parse_has(tokens),
@@ -147,6 +434,15 @@ fn parse_command(tokens: &[String]) -> Result<Command, CommandError> {
 		"ttl" => parse_ttl(tokens),
 		"size" => parse_size(tokens),
 
+		"mget" => parse_mget(tokens),
+		"mset" => parse_mset(tokens, quoted),
+		"mdel" => parse_mdel(tokens),
+		"mhas" => parse_mhas(tokens),
+
+		"edit" => parse_edit(tokens),
+		"digest" => parse_digest(tokens),
+		"diff" => parse_diff(tokens),
+
 		"wipe" => parse_wipe(tokens),
 
 		"resize" => parse_resize(tokens),
@@ -154,6 +450,18 @@ fn parse_command(tokens: &[String]) -> Result<Command, CommandError> {
 
 		"status" => parse_status(tokens),
 
+		"connect" => parse_connect(tokens),
+		"disconnect" => parse_disconnect(tokens),
+		"reconnect" => parse_reconnect(tokens),
+
+		"open" => parse_open(tokens),
+		"use" => parse_use(tokens),
+		"close" => parse_close(tokens),
+		"connections" => parse_connections(tokens),
+		"shard" => parse_shard(tokens),
+
+		"record" => parse_record(tokens),
+
 		"h" | "help" => Ok(Command::Cli(CliCommand::Help)),
 
 		"clear" => Ok(Command::Cli(CliCommand::Clear)),
@@ -164,6 +472,17 @@ fn parse_command(tokens: &[String]) -> Result<Command, CommandError> {
 	}
 }
 
+fn parse_targeted(name: &str, tokens: &[String], quoted: &[bool]) -> Result<Command, CommandError> {
+	if name.is_empty() || tokens.is_empty() {
+		return Err(CommandError::InvalidArguments("@"));
+	}
+
+	match parse_command(tokens, quoted)? {
+		Command::Client(command) => Ok(Command::Targeted(name.to_owned(), command)),
+		_ => Err(CommandError::InvalidTarget),
+	}
+}
+
 fn parse_ping(tokens: &[String]) -> Result<Command, CommandError> {
 	if tokens.len() != 1 {
 		return Err(CommandError::InvalidArguments("ping"));
@@ -189,34 +508,22 @@ fn parse_auth(tokens: &[String]) -> Result<Command, CommandError> {
 }
 
 fn parse_get(tokens: &[String]) -> Result<Command, CommandError> {
-	if tokens.len() != 2 {
+	if tokens.len() < 2 {
 		return Err(CommandError::InvalidArguments("get"));
 	}
 
-	Ok(Command::Client(ClientCommand::Get(tokens[1].clone())))
+	let view = View::parse(&tokens[2..], "get")?;
+
+	Ok(Command::Client(ClientCommand::Get(tokens[1].clone(), view)))
 }
 
-fn parse_set(tokens: &[String]) -> Result<Command, CommandError> {
-	if tokens.len() < 3 || tokens.len() > 4 {
+fn parse_set(tokens: &[String], quoted: &[bool]) -> Result<Command, CommandError> {
+	if tokens.len() < 3 || tokens.len() > 5 {
 		return Err(CommandError::InvalidArguments("set"));
 	}
 
-	let value = tokens[2].clone();
-
-	let ttl_value = if tokens.len() == 4 {
-		tokens[3].parse::<u32>()
-	} else {
-		Ok(0)
-	};
-
-	if ttl_value.is_err() {
-		return Err(CommandError::InvalidTtl);
-	}
-
-	let ttl = match ttl_value.unwrap() {
-		0 => None,
-		value => Some(value),
-	};
+	let value = parse_value(&tokens[2], quoted[2])?;
+	let ttl = ttl::parse(&tokens[3..])?;
 
 	Ok(Command::Client(ClientCommand::Set(
 		tokens[1].clone(),
@@ -225,6 +532,46 @@ fn parse_set(tokens: &[String]) -> Result<Command, CommandError> {
 	)))
 }
 
+/// Parses the value of a `set` command, which is either read from the file
+/// at the path following an `@`, read from stdin if it is `-`, encoded as
+/// the type preceding a `:` (e.g., `u64le:42`), or used as it is. A quoted
+/// value (e.g., `"@kia"`) is always used as it is.
+fn parse_value(token: &str, is_quoted: bool) -> Result<Vec<u8>, CommandError> {
+	if is_quoted {
+		return Ok(token.as_bytes().to_vec());
+	}
+
+	if let Some(path) = token.strip_prefix('@') {
+		return fs::read(path).map_err(|_| CommandError::InvalidValueFile);
+	}
+
+	if token == "-" {
+		let mut stdin = io::stdin();
+
+		if stdin.is_terminal() {
+			return Err(CommandError::StdinIsTerminal);
+		}
+
+		let mut value = Vec::new();
+
+		stdin
+			.read_to_end(&mut value)
+			.map_err(|_| CommandError::InvalidValueFile)?;
+
+		return Ok(value);
+	}
+
+	let codec = token
+		.split_once(':')
+		.and_then(|(codec, value)| Some((Codec::from_str(codec).ok()?, value)));
+
+	if let Some((codec, value)) = codec {
+		return codec.encode(value);
+	}
+
+	Ok(token.as_bytes().to_vec())
+}
+
 fn parse_del(tokens: &[String]) -> Result<Command, CommandError> {
 	if tokens.len() != 2 {
 		return Err(CommandError::InvalidArguments("del"));
@@ -242,32 +589,21 @@ fn parse_has(tokens: &[String]) -> Result<Command, CommandError> {
 }
 
 fn parse_peek(tokens: &[String]) -> Result<Command, CommandError> {
-	if tokens.len() != 2 {
+	if tokens.len() < 2 {
 		return Err(CommandError::InvalidArguments("peek"));
 	}
 
-	Ok(Command::Client(ClientCommand::Peek(tokens[1].clone())))
+	let view = View::parse(&tokens[2..], "peek")?;
+
+	Ok(Command::Client(ClientCommand::Peek(tokens[1].clone(), view)))
 }
 
 fn parse_ttl(tokens: &[String]) -> Result<Command, CommandError> {
-	if tokens.len() < 2 || tokens.len() > 3 {
+	if tokens.len() < 2 || tokens.len() > 4 {
 		return Err(CommandError::InvalidArguments("ttl"));
 	}
 
-	let ttl_value = if tokens.len() == 3 {
-		tokens[2].parse::<u32>()
-	} else {
-		Ok(0)
-	};
-
-	if ttl_value.is_err() {
-		return Err(CommandError::InvalidTtl);
-	}
-
-	let ttl = match ttl_value.unwrap() {
-		0 => None,
-		value => Some(value),
-	};
+	let ttl = ttl::parse(&tokens[2..])?;
 
 	Ok(Command::Client(ClientCommand::Ttl(tokens[1].clone(), ttl)))
 }
@@ -280,6 +616,102 @@ fn parse_size(tokens: &[String]) -> Result<Command, CommandError> {
 	Ok(Command::Client(ClientCommand::Size(tokens[1].clone())))
 }
 
+fn parse_mget(tokens: &[String]) -> Result<Command, CommandError> {
+	if tokens.len() < 2 {
+		return Err(CommandError::InvalidArguments("mget"));
+	}
+
+	Ok(Command::Client(ClientCommand::MGet(tokens[1..].to_vec())))
+}
+
+fn parse_mset(tokens: &[String], quoted: &[bool]) -> Result<Command, CommandError> {
+	let num_args = tokens.len() - 1;
+
+	// a TTL shared by every key follows the last pair, so an odd number of
+	// arguments ends with a TTL, and an even number ends with a pair unless
+	// it ends with an expiry time (a key named `at` can be quoted to keep
+	// it from being read as one)
+	let num_ttl_tokens = match num_args % 2 {
+		1 => 1,
+		_ if num_args >= 4 && !quoted[tokens.len() - 2] && ttl::is_expiry(tokens) => 2,
+		_ => 0,
+	};
+
+	let ttl_index = tokens.len() - num_ttl_tokens;
+	let pair_tokens = &tokens[1..ttl_index];
+
+	if pair_tokens.is_empty() || !pair_tokens.len().is_multiple_of(2) {
+		return Err(CommandError::InvalidArguments("mset"));
+	}
+
+	// a token which is not a TTL is most likely a key without a value
+	let ttl = match ttl::parse(&tokens[ttl_index..]) {
+		Err(CommandError::InvalidTtl) => return Err(CommandError::InvalidArguments("mset")),
+		ttl => ttl?,
+	};
+
+	let pairs = pair_tokens
+		.chunks(2)
+		.zip(quoted[1..].chunks(2))
+		.map(|(pair, quoted)| Ok((pair[0].clone(), parse_value(&pair[1], quoted[1])?)))
+		.collect::<Result<Vec<_>, CommandError>>()?;
+
+	Ok(Command::Client(ClientCommand::MSet(pairs, ttl)))
+}
+
+fn parse_mdel(tokens: &[String]) -> Result<Command, CommandError> {
+	if tokens.len() < 2 {
+		return Err(CommandError::InvalidArguments("mdel"));
+	}
+
+	Ok(Command::Client(ClientCommand::MDel(tokens[1..].to_vec())))
+}
+
+fn parse_mhas(tokens: &[String]) -> Result<Command, CommandError> {
+	if tokens.len() < 2 {
+		return Err(CommandError::InvalidArguments("mhas"));
+	}
+
+	Ok(Command::Client(ClientCommand::MHas(tokens[1..].to_vec())))
+}
+
+fn parse_edit(tokens: &[String]) -> Result<Command, CommandError> {
+	if tokens.len() < 2 || tokens.len() > 4 {
+		return Err(CommandError::InvalidArguments("edit"));
+	}
+
+	let ttl = ttl::parse(&tokens[2..])?;
+
+	Ok(Command::Client(ClientCommand::Edit(tokens[1].clone(), ttl)))
+}
+
+fn parse_digest(tokens: &[String]) -> Result<Command, CommandError> {
+	if tokens.len() < 2 || tokens.len() > 3 {
+		return Err(CommandError::InvalidArguments("digest"));
+	}
+
+	let algorithm = match tokens.get(2) {
+		Some(algorithm) => Algorithm::from_str(algorithm)?,
+		None => Algorithm::default(),
+	};
+
+	Ok(Command::Client(ClientCommand::Digest(
+		tokens[1].clone(),
+		algorithm,
+	)))
+}
+
+fn parse_diff(tokens: &[String]) -> Result<Command, CommandError> {
+	if tokens.len() != 3 {
+		return Err(CommandError::InvalidArguments("diff"));
+	}
+
+	Ok(Command::Client(ClientCommand::Diff(
+		tokens[1].clone(),
+		tokens[2].clone(),
+	)))
+}
+
 fn parse_wipe(tokens: &[String]) -> Result<Command, CommandError> {
 	if tokens.len() != 1 {
 		return Err(CommandError::InvalidArguments("wipe"));
@@ -293,19 +725,20 @@ fn parse_resize(tokens: &[String]) -> Result<Command, CommandError> {
 		return Err(CommandError::InvalidArguments("resize"));
 	}
 
-	match parse_input_size(tokens[1..].join(" ")) {
-		Ok(size) => Ok(Command::Client(ClientCommand::Resize(size))),
+	let size = CacheSize::from_str(&tokens[1..].join(" "))?;
 
-		Err(_) => Err(CommandError::InvalidCacheSize),
-	}
+	Ok(Command::Client(ClientCommand::Resize(size)))
 }
 
 fn parse_policy(tokens: &[String]) -> Result<Command, CommandError> {
-	if tokens.len() != 2 {
+	if tokens.len() > 2 {
 		return Err(CommandError::InvalidArguments("policy"));
 	}
 
-	let policy = PaperPolicy::from_str(&tokens[1]).map_err(|_| CommandError::InvalidPolicy)?;
+	let policy = match tokens.get(1) {
+		Some(policy) => Some(PaperPolicy::from_str(policy).map_err(|_| CommandError::InvalidPolicy)?),
+		None => None,
+	};
 
 	Ok(Command::Client(ClientCommand::Policy(policy)))
 }
@@ -323,3 +756,206 @@ fn parse_status(tokens: &[String]) -> Result<Command, CommandError> {
 
 	Ok(Command::Client(ClientCommand::Status(watch)))
 }
+
+fn parse_connect(tokens: &[String]) -> Result<Command, CommandError> {
+	if tokens.len() != 2 {
+		return Err(CommandError::InvalidArguments("connect"));
+	}
+
+	Ok(Command::Cli(CliCommand::Connect(tokens[1].clone())))
+}
+
+fn parse_disconnect(tokens: &[String]) -> Result<Command, CommandError> {
+	if tokens.len() != 1 {
+		return Err(CommandError::InvalidArguments("disconnect"));
+	}
+
+	Ok(Command::Cli(CliCommand::Disconnect))
+}
+
+fn parse_reconnect(tokens: &[String]) -> Result<Command, CommandError> {
+	if tokens.len() != 1 {
+		return Err(CommandError::InvalidArguments("reconnect"));
+	}
+
+	Ok(Command::Cli(CliCommand::Reconnect))
+}
+
+fn parse_open(tokens: &[String]) -> Result<Command, CommandError> {
+	if tokens.len() != 3 {
+		return Err(CommandError::InvalidArguments("open"));
+	}
+
+	Ok(Command::Cli(CliCommand::Open(
+		tokens[1].clone(),
+		tokens[2].clone(),
+	)))
+}
+
+fn parse_use(tokens: &[String]) -> Result<Command, CommandError> {
+	if tokens.len() != 2 {
+		return Err(CommandError::InvalidArguments("use"));
+	}
+
+	Ok(Command::Cli(CliCommand::Use(tokens[1].clone())))
+}
+
+fn parse_close(tokens: &[String]) -> Result<Command, CommandError> {
+	if tokens.len() != 2 {
+		return Err(CommandError::InvalidArguments("close"));
+	}
+
+	Ok(Command::Cli(CliCommand::Close(tokens[1].clone())))
+}
+
+fn parse_connections(tokens: &[String]) -> Result<Command, CommandError> {
+	if tokens.len() != 1 {
+		return Err(CommandError::InvalidArguments("connections"));
+	}
+
+	Ok(Command::Cli(CliCommand::Connections))
+}
+
+fn parse_shard(tokens: &[String]) -> Result<Command, CommandError> {
+	if tokens.len() != 2 {
+		return Err(CommandError::InvalidArguments("shard"));
+	}
+
+	Ok(Command::Cli(CliCommand::Shard(tokens[1].clone())))
+}
+
+fn parse_record(tokens: &[String]) -> Result<Command, CommandError> {
+	let path = match (tokens.len(), tokens.get(1).map(String::as_str)) {
+		(3, Some("start")) => Some(tokens[2].clone()),
+		(2, Some("stop")) => None,
+
+		_ => return Err(CommandError::InvalidArguments("record")),
+	};
+
+	Ok(Command::Cli(CliCommand::Record(path)))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		addr::ServerAddr,
+		command::{
+			ClientCommand,
+			Command,
+			error::CommandError,
+			parser::{CommandParser, parse_mset},
+		},
+	};
+
+	type Pairs = Vec<(String, Vec<u8>)>;
+
+	/// Parses the line the way a line entered in a session is parsed.
+	fn parse(line: &str) -> Result<Command, CommandError> {
+		let addr = ServerAddr::new("127.0.0.1", 3145)?;
+		CommandParser::new(&addr).parse(line)
+	}
+
+	/// Returns the key and value of each `set` command the line is parsed
+	/// into.
+	fn sets(line: &str) -> Pairs {
+		let commands = match parse(line) {
+			Ok(Command::Expanded(_, commands)) => commands,
+			Ok(command) => vec![command],
+			Err(err) => panic!("{err}"),
+		};
+
+		commands
+			.into_iter()
+			.map(|command| match command {
+				Command::Client(ClientCommand::Set(key, value, _)) => (key, value),
+				_ => panic!("expected a set command"),
+			})
+			.collect()
+	}
+
+	/// Parses the `mset` command, with the tokens in `quoted` quoted.
+	fn mset(line: &str, quoted: &[usize]) -> Result<(Pairs, Option<u32>), CommandError> {
+		let tokens = line.split(' ').map(String::from).collect::<Vec<_>>();
+
+		let quoted = (0..tokens.len())
+			.map(|index| quoted.contains(&index))
+			.collect::<Vec<_>>();
+
+		match parse_mset(&tokens, &quoted)? {
+			Command::Client(ClientCommand::MSet(pairs, ttl)) => Ok((pairs, ttl)),
+			_ => panic!("expected an mset command"),
+		}
+	}
+
+	fn pairs(pairs: &[(&str, &str)]) -> Pairs {
+		pairs
+			.iter()
+			.map(|(key, value)| (key.to_string(), value.as_bytes().to_vec()))
+			.collect()
+	}
+
+	#[test]
+	fn it_parses_pairs() {
+		assert_eq!(mset("mset a 1", &[]), Ok((pairs(&[("a", "1")]), None)));
+		assert_eq!(mset("mset a 1 b 2", &[]), Ok((pairs(&[("a", "1"), ("b", "2")]), None)));
+		assert_eq!(mset("mset a 1 b 2 1h", &[]), Ok((pairs(&[("a", "1"), ("b", "2")]), Some(3600))));
+	}
+
+	#[test]
+	fn it_parses_keys_named_at() {
+		assert_eq!(mset("mset at 1", &[]), Ok((pairs(&[("at", "1")]), None)));
+		assert_eq!(mset("mset a 1 at 2", &[]), Ok((pairs(&[("a", "1"), ("at", "2")]), None)));
+
+		assert_eq!(
+			mset("mset a 1 at 2030-01-01", &[3]),
+			Ok((pairs(&[("a", "1"), ("at", "2030-01-01")]), None)),
+		);
+
+		let (parsed, ttl) = mset("mset a 1 at 2100-01-01", &[]).unwrap();
+
+		assert_eq!(parsed, pairs(&[("a", "1")]));
+		assert!(ttl.is_some());
+	}
+
+	#[test]
+	fn it_rejects_incomplete_pairs() {
+		for line in ["mset", "mset a", "mset a 1 b", "mset a 1 b c d", "mset a 1 1x"] {
+			assert_eq!(mset(line, &[]), Err(CommandError::InvalidArguments("mset")), "{line}");
+		}
+
+		assert_eq!(mset("mset a 1 4294967296", &[]), Err(CommandError::TtlTooLarge));
+	}
+
+	#[test]
+	fn it_expands_keys() {
+		assert_eq!(sets("set user:{1..2} x"), pairs(&[("user:1", "x"), ("user:2", "x")]));
+
+		match parse("del tmp:{a,b}") {
+			Ok(Command::Expanded(2, commands)) => match commands.as_slice() {
+				[Command::Client(ClientCommand::MDel(keys))] => assert_eq!(keys, &["tmp:a", "tmp:b"]),
+				_ => panic!("expected an mdel command"),
+			},
+
+			_ => panic!("expected an expanded command"),
+		}
+	}
+
+	#[test]
+	fn it_stores_values_as_they_are() {
+		assert_eq!(sets("set k {x,y}"), pairs(&[("k", "{x,y}")]));
+		assert_eq!(sets("set k v{1..3}"), pairs(&[("k", "v{1..3}")]));
+
+		assert_eq!(
+			sets("set user:{1..2} {x,y}"),
+			pairs(&[("user:1", "{x,y}"), ("user:2", "{x,y}")]),
+		);
+
+		match parse("mset a {x,y} b {1..3}") {
+			Ok(Command::Client(ClientCommand::MSet(parsed, None))) => {
+				assert_eq!(parsed, pairs(&[("a", "{x,y}"), ("b", "{1..3}")]));
+			},
+
+			_ => panic!("expected an mset command"),
+		}
+	}
+}
@@ -0,0 +1,131 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::command::error::CommandError;
+
+/// A path to a field of a JSON value (e.g., `.profile.name` or
+/// `.items[0].id`). The path `.` selects the whole value.
+#[derive(Clone)]
+pub struct JsonPath {
+	segments: Vec<Segment>,
+}
+
+#[derive(Clone)]
+enum Segment {
+	Field(String),
+	Index(usize),
+}
+
+impl JsonPath {
+	/// Returns the field of the value at the path, if it exists.
+	pub fn select<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+		self.segments
+			.iter()
+			.try_fold(value, |value, segment| match segment {
+				Segment::Field(field) => value.get(field),
+				Segment::Index(index) => value.get(index),
+			})
+	}
+}
+
+impl FromStr for JsonPath {
+	type Err = CommandError;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		let Some(mut rest) = value.strip_prefix('.') else {
+			return Err(CommandError::InvalidJsonPath);
+		};
+
+		let mut segments = Vec::new();
+
+		while !rest.is_empty() {
+			if let Some(index_rest) = rest.strip_prefix('[') {
+				let (index, index_rest) = index_rest
+					.split_once(']')
+					.ok_or(CommandError::InvalidJsonPath)?;
+
+				let index = index
+					.parse::<usize>()
+					.map_err(|_| CommandError::InvalidJsonPath)?;
+
+				segments.push(Segment::Index(index));
+				rest = index_rest.strip_prefix('.').unwrap_or(index_rest);
+
+				continue;
+			}
+
+			let end = rest.find(['.', '[']).unwrap_or(rest.len());
+			let (field, field_rest) = rest.split_at(end);
+
+			if field.is_empty() {
+				return Err(CommandError::InvalidJsonPath);
+			}
+
+			segments.push(Segment::Field(field.to_owned()));
+
+			rest = match field_rest.strip_prefix('.') {
+				Some("") => return Err(CommandError::InvalidJsonPath),
+				Some(field_rest) => field_rest,
+				None => field_rest,
+			};
+		}
+
+		Ok(JsonPath {
+			segments,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+
+	use serde_json::{Value, json};
+
+	use crate::command::{error::CommandError, path::JsonPath};
+
+	fn select(path: &str, value: &Value) -> Result<Option<Value>, CommandError> {
+		Ok(JsonPath::from_str(path)?.select(value).cloned())
+	}
+
+	#[test]
+	fn it_selects_fields() {
+		let value = json!({
+			"profile": {"name": "kia", "日本": 1},
+			"items": [{"id": 1}, {"id": 2}],
+			"matrix": [[1, 2], [3, 4]],
+		});
+
+		assert_eq!(select(".", &value), Ok(Some(value.clone())));
+		assert_eq!(select(".profile.name", &value), Ok(Some(json!("kia"))));
+		assert_eq!(select(".profile.日本", &value), Ok(Some(json!(1))));
+		assert_eq!(select(".items[1].id", &value), Ok(Some(json!(2))));
+		assert_eq!(select(".items.[0].id", &value), Ok(Some(json!(1))));
+		assert_eq!(select(".matrix[1][0]", &value), Ok(Some(json!(3))));
+	}
+
+	#[test]
+	fn it_selects_missing_fields() {
+		let value = json!({"items": [1], "name": "kia"});
+
+		assert_eq!(select(".missing", &value), Ok(None));
+		assert_eq!(select(".items[1]", &value), Ok(None));
+		assert_eq!(select(".name.first", &value), Ok(None));
+		assert_eq!(select(".name[0]", &value), Ok(None));
+	}
+
+	#[test]
+	fn it_rejects_invalid_paths() {
+		for path in ["", "name", "..name", ".name.", ".items[", ".items[x]", ".items[-1]"] {
+			assert!(JsonPath::from_str(path).is_err(), "{path}");
+		}
+	}
+}
@@ -0,0 +1,207 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	fmt::{self, Display},
+	str::FromStr,
+};
+
+use kwik::fmt::memory;
+use paper_client::Status;
+use parse_size::parse_size;
+
+use crate::command::error::CommandError;
+
+/// The size a cache is resized to, either as it is or relative to the
+/// cache's current maximum size.
+#[derive(Clone, Copy)]
+pub enum CacheSize {
+	Absolute(u64),
+
+	/// Increases the size by the supplied number of bytes (e.g., `+512MiB`).
+	Grow(u64),
+
+	/// Decreases the size by the supplied number of bytes (e.g., `-1GiB`).
+	Shrink(u64),
+
+	/// Multiplies the size by the supplied factor (e.g., `2x` or `-10%`).
+	Scale(f64),
+}
+
+/// The sizes of a cache before and after it was resized.
+pub struct Resize {
+	old_size:  u64,
+	new_size:  u64,
+	used_size: u64,
+}
+
+impl CacheSize {
+	/// Returns the new maximum size of a cache which currently has the
+	/// supplied maximum size.
+	pub fn resolve(&self, max_size: u64) -> Result<u64, CommandError> {
+		match self {
+			CacheSize::Absolute(size) => Ok(*size),
+
+			CacheSize::Grow(size) => max_size
+				.checked_add(*size)
+				.ok_or(CommandError::InvalidCacheSize),
+
+			CacheSize::Shrink(size) => max_size
+				.checked_sub(*size)
+				.filter(|size| *size > 0)
+				.ok_or(CommandError::CacheSizeTooSmall),
+
+			CacheSize::Scale(factor) => {
+				let size = (max_size as f64 * factor).round();
+
+				if size < 1.0 {
+					return Err(CommandError::CacheSizeTooSmall);
+				}
+
+				if size >= u64::MAX as f64 {
+					return Err(CommandError::InvalidCacheSize);
+				}
+
+				Ok(size as u64)
+			},
+		}
+	}
+}
+
+impl FromStr for CacheSize {
+	type Err = CommandError;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		let value = value.trim();
+
+		if let Some(factor) = value.strip_suffix(['x', 'X']) {
+			return scale(factor.trim().parse::<f64>().ok());
+		}
+
+		if let Some(percent) = value.strip_suffix('%') {
+			let percent = percent.trim();
+
+			let factor = match percent.split_at_checked(1) {
+				Some(("+", percent)) => percent.trim().parse::<f64>().ok().map(|p| 1.0 + p / 100.0),
+				Some(("-", percent)) => percent.trim().parse::<f64>().ok().map(|p| 1.0 - p / 100.0),
+				_ => percent.parse::<f64>().ok().map(|p| p / 100.0),
+			};
+
+			return scale(factor);
+		}
+
+		let size = |value: &str| parse_size(value.trim()).map_err(|_| CommandError::InvalidCacheSize);
+
+		match value.split_at_checked(1) {
+			Some(("+", value)) => Ok(CacheSize::Grow(size(value)?)),
+			Some(("-", value)) => Ok(CacheSize::Shrink(size(value)?)),
+			_ => Ok(CacheSize::Absolute(size(value)?)),
+		}
+	}
+}
+
+impl Display for CacheSize {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			CacheSize::Absolute(size) => write!(f, "{}", memory(*size, Some(2))),
+			CacheSize::Grow(size) => write!(f, "+{}", memory(*size, Some(2))),
+			CacheSize::Shrink(size) => write!(f, "-{}", memory(*size, Some(2))),
+			CacheSize::Scale(factor) => write!(f, "{factor}x"),
+		}
+	}
+}
+
+impl Resize {
+	pub fn new(status: &Status, size: CacheSize) -> Result<Self, CommandError> {
+		Ok(Resize {
+			old_size:  status.max_size(),
+			new_size:  size.resolve(status.max_size())?,
+			used_size: status.used_size(),
+		})
+	}
+
+	pub fn old_size(&self) -> u64 {
+		self.old_size
+	}
+
+	pub fn new_size(&self) -> u64 {
+		self.new_size
+	}
+
+	pub fn used_size(&self) -> u64 {
+		self.used_size
+	}
+
+	/// Returns `true` if the new size is smaller than the size of the cached
+	/// objects, so that objects are evicted to fit in it.
+	pub fn is_evicting(&self) -> bool {
+		self.new_size < self.used_size
+	}
+}
+
+fn scale(factor: Option<f64>) -> Result<CacheSize, CommandError> {
+	match factor {
+		Some(factor) if factor.is_finite() && factor > 0.0 => Ok(CacheSize::Scale(factor)),
+		_ => Err(CommandError::InvalidCacheSize),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+
+	use crate::command::{error::CommandError, resize::CacheSize};
+
+	const GIB: u64 = 1 << 30;
+
+	fn resolve(size: &str, max_size: u64) -> Result<u64, CommandError> {
+		CacheSize::from_str(size)?.resolve(max_size)
+	}
+
+	#[test]
+	fn it_parses_absolute_sizes() {
+		assert_eq!(resolve("1GiB", 5), Ok(GIB));
+		assert_eq!(resolve(" 512 KiB ", 5), Ok(512 << 10));
+		assert_eq!(resolve("1000", 5), Ok(1000));
+	}
+
+	#[test]
+	fn it_parses_relative_sizes() {
+		assert_eq!(resolve("+512MiB", GIB), Ok(GIB + (512 << 20)));
+		assert_eq!(resolve("-512MiB", GIB), Ok(512 << 20));
+		assert_eq!(resolve("2x", GIB), Ok(2 * GIB));
+		assert_eq!(resolve("0.5X", GIB), Ok(GIB / 2));
+		assert_eq!(resolve("50%", GIB), Ok(GIB / 2));
+		assert_eq!(resolve("+50%", GIB), Ok(GIB + GIB / 2));
+		assert_eq!(resolve("-25%", GIB), Ok(GIB - GIB / 4));
+	}
+
+	#[test]
+	fn it_rejects_invalid_sizes() {
+		for size in ["", "big", "0x", "-1x", "x", "%", "+", "1 parsecs", "日本", "infx"] {
+			assert!(CacheSize::from_str(size).is_err(), "{size}");
+		}
+	}
+
+	#[test]
+	fn it_rejects_sizes_which_do_not_fit() {
+		assert_eq!(resolve("-1GiB", GIB), Err(CommandError::CacheSizeTooSmall));
+		assert_eq!(resolve("-2GiB", GIB), Err(CommandError::CacheSizeTooSmall));
+		assert_eq!(resolve("-100%", GIB), Err(CommandError::InvalidCacheSize));
+		assert_eq!(resolve("+1", u64::MAX), Err(CommandError::InvalidCacheSize));
+		assert_eq!(resolve("2x", u64::MAX), Err(CommandError::InvalidCacheSize));
+	}
+
+	#[test]
+	fn it_formats_sizes() {
+		let size = |size: &str| CacheSize::from_str(size).map(|size| size.to_string());
+
+		assert_eq!(size("1GiB"), Ok("1.00 GiB".into()));
+		assert_eq!(size("+512MiB"), Ok("+512.00 MiB".into()));
+		assert_eq!(size("2x"), Ok("2x".into()));
+	}
+}
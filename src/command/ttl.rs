@@ -0,0 +1,230 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::command::error::CommandError;
+
+/// The formats of expiry times which include a UTC offset (e.g.,
+/// `2026-10-18T09:00Z` or `2026-10-18T09:00:30+02:00`).
+const OFFSET_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M%#z", "%Y-%m-%dT%H:%M:%S%#z"];
+
+/// The formats of expiry times in the local time zone.
+const LOCAL_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S"];
+
+/// Parses the TTL at the end of a command, which is one of:
+///  - a number of seconds (e.g., `90`),
+///  - a duration of days, hours, minutes and seconds (e.g., `1h30m`),
+///  - `none` or `persist` for no TTL, or
+///  - `at` followed by the time at which the key expires (e.g.,
+///    `at 2026-10-18T09:00Z`), which is converted to a number of seconds.
+///
+/// A TTL of zero, like no TTL at all, means the key does not expire.
+pub fn parse(tokens: &[String]) -> Result<Option<u32>, CommandError> {
+	let seconds = match tokens {
+		[] => return Ok(None),
+
+		[keyword] if is_keyword(keyword, &["none", "persist"]) => return Ok(None),
+		[keyword, time] if is_keyword(keyword, &["at"]) => seconds_until(time)?,
+		[duration] => parse_duration(duration)?,
+
+		_ => return Err(CommandError::InvalidTtl),
+	};
+
+	match u32::try_from(seconds) {
+		Ok(0) => Ok(None),
+		Ok(seconds) => Ok(Some(seconds)),
+		Err(_) => Err(CommandError::TtlTooLarge),
+	}
+}
+
+/// Returns `true` if the tokens end with an expiry time (i.e., `at`
+/// followed by a time), which takes up two tokens rather than one.
+pub fn is_expiry(tokens: &[String]) -> bool {
+	match tokens {
+		[.., keyword, time] => is_keyword(keyword, &["at"]) && parse_time(time).is_ok(),
+		_ => false,
+	}
+}
+
+/// Parses a number of seconds, or a sequence of amounts each followed by a
+/// unit (`d`, `h`, `m` or `s`).
+fn parse_duration(duration: &str) -> Result<u64, CommandError> {
+	let is_seconds = duration.chars().all(|c| c.is_ascii_digit());
+
+	if is_seconds && !duration.is_empty() {
+		return duration
+			.parse::<u64>()
+			.map_err(|_| CommandError::TtlTooLarge);
+	}
+
+	let mut seconds: u64 = 0;
+	let mut amount = String::new();
+
+	for c in duration.chars() {
+		if c.is_ascii_digit() {
+			amount.push(c);
+			continue;
+		}
+
+		let unit_seconds = match c.to_ascii_lowercase() {
+			'd' => 86_400,
+			'h' => 3_600,
+			'm' => 60,
+			's' => 1,
+
+			_ => return Err(CommandError::InvalidTtl),
+		};
+
+		let value = match amount.parse::<u64>() {
+			Ok(value) => value,
+			Err(_) if amount.is_empty() => return Err(CommandError::InvalidTtl),
+			Err(_) => return Err(CommandError::TtlTooLarge),
+		};
+
+		seconds = value
+			.checked_mul(unit_seconds)
+			.and_then(|value| seconds.checked_add(value))
+			.ok_or(CommandError::TtlTooLarge)?;
+
+		amount.clear();
+	}
+
+	// every amount must be followed by a unit
+	if !amount.is_empty() || duration.is_empty() {
+		return Err(CommandError::InvalidTtl);
+	}
+
+	Ok(seconds)
+}
+
+/// Returns the number of seconds from now until the time, rounded up so
+/// that the key does not expire early.
+fn seconds_until(time: &str) -> Result<u64, CommandError> {
+	let expiry = parse_time(time)?;
+	let milliseconds = (expiry - Utc::now()).num_milliseconds();
+
+	if milliseconds <= 0 {
+		return Err(CommandError::ExpiryInPast);
+	}
+
+	Ok((milliseconds as u64).div_ceil(1000))
+}
+
+fn parse_time(time: &str) -> Result<DateTime<Utc>, CommandError> {
+	if let Ok(expiry) = DateTime::parse_from_rfc3339(time) {
+		return Ok(expiry.to_utc());
+	}
+
+	for format in OFFSET_FORMATS {
+		if let Ok(expiry) = DateTime::parse_from_str(time, format) {
+			return Ok(expiry.to_utc());
+		}
+	}
+
+	let local_time = LOCAL_FORMATS
+		.iter()
+		.find_map(|format| NaiveDateTime::parse_from_str(time, format).ok())
+		.or_else(|| {
+			NaiveDate::parse_from_str(time, "%Y-%m-%d")
+				.ok()
+				.and_then(|date| date.and_hms_opt(0, 0, 0))
+		})
+		.ok_or(CommandError::InvalidExpiry)?;
+
+	// a local time which is repeated when clocks go back is taken to be the
+	// first one, and one which is skipped when clocks go forward is invalid
+	Local
+		.from_local_datetime(&local_time)
+		.earliest()
+		.map(|expiry| expiry.to_utc())
+		.ok_or(CommandError::InvalidExpiry)
+}
+
+fn is_keyword(token: &str, keywords: &[&str]) -> bool {
+	keywords
+		.iter()
+		.any(|keyword| token.eq_ignore_ascii_case(keyword))
+}
+
+#[cfg(test)]
+mod tests {
+	use chrono::{Duration, SecondsFormat, Utc};
+
+	use crate::command::{
+		error::CommandError,
+		ttl::{is_expiry, parse},
+	};
+
+	fn tokens(tokens: &[&str]) -> Vec<String> {
+		tokens.iter().map(|token| token.to_string()).collect()
+	}
+
+	#[test]
+	fn it_parses_seconds() {
+		assert_eq!(parse(&tokens(&["90"])), Ok(Some(90)));
+		assert_eq!(parse(&tokens(&["4294967295"])), Ok(Some(u32::MAX)));
+	}
+
+	#[test]
+	fn it_parses_durations() {
+		assert_eq!(parse(&tokens(&["1h30m"])), Ok(Some(5400)));
+		assert_eq!(parse(&tokens(&["2d"])), Ok(Some(172_800)));
+		assert_eq!(parse(&tokens(&["1M30S"])), Ok(Some(90)));
+		assert_eq!(parse(&tokens(&["1h1h"])), Ok(Some(7200)));
+	}
+
+	#[test]
+	fn it_parses_no_ttl() {
+		assert_eq!(parse(&[]), Ok(None));
+		assert_eq!(parse(&tokens(&["none"])), Ok(None));
+		assert_eq!(parse(&tokens(&["PERSIST"])), Ok(None));
+		assert_eq!(parse(&tokens(&["0"])), Ok(None));
+		assert_eq!(parse(&tokens(&["0h0m"])), Ok(None));
+	}
+
+	#[test]
+	fn it_rejects_invalid_ttls() {
+		for ttl in ["", "h", "1x", "1h30", "-5", "1.5h", "日本"] {
+			assert_eq!(parse(&tokens(&[ttl])), Err(CommandError::InvalidTtl), "{ttl}");
+		}
+
+		assert_eq!(parse(&tokens(&["1", "2"])), Err(CommandError::InvalidTtl));
+	}
+
+	#[test]
+	fn it_rejects_ttls_which_are_too_large() {
+		assert_eq!(parse(&tokens(&["4294967296"])), Err(CommandError::TtlTooLarge));
+		assert_eq!(parse(&tokens(&["99999999999999999999"])), Err(CommandError::TtlTooLarge));
+		assert_eq!(parse(&tokens(&["999999999999999d"])), Err(CommandError::TtlTooLarge));
+	}
+
+	#[test]
+	fn it_parses_expiry_times() {
+		let time = (Utc::now() + Duration::hours(1)).to_rfc3339_opts(SecondsFormat::Secs, true);
+		let ttl = parse(&tokens(&["at", &time])).unwrap().unwrap();
+
+		assert!((3590..=3600).contains(&ttl), "{ttl}");
+
+		assert_eq!(
+			parse(&tokens(&["at", "2000-01-01T00:00Z"])),
+			Err(CommandError::ExpiryInPast),
+		);
+
+		assert_eq!(parse(&tokens(&["at", "tomorrow"])), Err(CommandError::InvalidExpiry));
+	}
+
+	#[test]
+	fn it_detects_expiry_times() {
+		assert!(is_expiry(&tokens(&["set", "key", "value", "at", "2026-10-18"])));
+		assert!(is_expiry(&tokens(&["AT", "2026-10-18"])));
+
+		assert!(!is_expiry(&tokens(&["set", "key", "value", "60"])));
+		assert!(!is_expiry(&tokens(&["mset", "key", "value", "at", "value"])));
+		assert!(!is_expiry(&tokens(&["at"])));
+	}
+}
@@ -0,0 +1,147 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	path::{Path, PathBuf},
+	str::FromStr,
+};
+
+use crate::command::{codec::Codec, error::CommandError, path::JsonPath};
+
+/// How a value returned by `get` or `peek` is displayed.
+#[derive(Clone, Default)]
+pub struct View {
+	encoding: Encoding,
+
+	/// The type the value is decoded as (e.g., `get hits as u64le`).
+	codec: Option<Codec>,
+
+	/// Whether the value is displayed as JSON even if it does not look like
+	/// a JSON object or array.
+	json:    bool,
+	compact: bool,
+
+	/// The field of a JSON value which is displayed instead of the whole
+	/// value (e.g., `get user:1 .profile.name`).
+	path: Option<JsonPath>,
+
+	/// The file to which the raw value is written instead of being
+	/// displayed.
+	out: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Encoding {
+	/// Text if the value is valid UTF-8, otherwise a hex dump.
+	#[default]
+	Auto,
+
+	Hex,
+	Base64,
+}
+
+impl View {
+	/// Parses the display options which follow the key of a `get` or `peek`
+	/// command.
+	pub fn parse(tokens: &[String], command: &'static str) -> Result<Self, CommandError> {
+		let mut view = View::default();
+		let mut tokens = tokens.iter();
+
+		while let Some(token) = tokens.next() {
+			match token.as_str() {
+				"--hex" => view.set_encoding(Encoding::Hex, command)?,
+				"--base64" => view.set_encoding(Encoding::Base64, command)?,
+
+				"--json" if !view.json => view.json = true,
+				"--compact" if !view.compact => view.compact = true,
+
+				"as" => match tokens.next() {
+					Some(codec) => view.set_codec(Codec::from_str(codec)?, command)?,
+					None => return Err(CommandError::InvalidArguments(command)),
+				},
+
+				"--out" | ">" => match tokens.next() {
+					Some(path) => view.set_out(path, command)?,
+					None => return Err(CommandError::InvalidArguments(command)),
+				},
+
+				token if token.starts_with('.') && view.path.is_none() => {
+					view.path = Some(JsonPath::from_str(token)?);
+				},
+
+				token => match token.strip_prefix('>') {
+					Some(path) => view.set_out(path, command)?,
+					None => return Err(CommandError::InvalidArguments(command)),
+				},
+			}
+		}
+
+		let is_json = view.json || view.compact || view.path.is_some();
+
+		if is_json && (view.encoding != Encoding::Auto || view.codec.is_some()) {
+			return Err(CommandError::InvalidArguments(command));
+		}
+
+		Ok(view)
+	}
+
+	pub fn encoding(&self) -> Encoding {
+		self.encoding
+	}
+
+	pub fn codec(&self) -> Option<Codec> {
+		self.codec
+	}
+
+	/// Returns `true` if the value must be displayed as JSON rather than
+	/// only if it looks like JSON.
+	pub fn is_json(&self) -> bool {
+		self.json || self.path.is_some()
+	}
+
+	pub fn is_compact(&self) -> bool {
+		self.compact
+	}
+
+	pub fn path(&self) -> Option<&JsonPath> {
+		self.path.as_ref()
+	}
+
+	pub fn out(&self) -> Option<&Path> {
+		self.out.as_deref()
+	}
+
+	fn set_encoding(&mut self, encoding: Encoding, command: &'static str) -> Result<(), CommandError> {
+		if self.encoding != Encoding::Auto || self.codec.is_some() {
+			return Err(CommandError::InvalidArguments(command));
+		}
+
+		self.encoding = encoding;
+
+		Ok(())
+	}
+
+	fn set_codec(&mut self, codec: Codec, command: &'static str) -> Result<(), CommandError> {
+		if self.encoding != Encoding::Auto || self.codec.is_some() {
+			return Err(CommandError::InvalidArguments(command));
+		}
+
+		self.codec = Some(codec);
+
+		Ok(())
+	}
+
+	fn set_out(&mut self, path: &str, command: &'static str) -> Result<(), CommandError> {
+		if self.out.is_some() || path.is_empty() {
+			return Err(CommandError::InvalidArguments(command));
+		}
+
+		self.out = Some(PathBuf::from(path));
+
+		Ok(())
+	}
+}
@@ -0,0 +1,392 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{mem, str::FromStr, time::Duration};
+
+use paper_client::{PaperClient, PaperClientError, error::PaperClientResult};
+use serde_json::Value;
+
+use crate::{
+	addr::ServerAddr,
+	audit::{self, AuditLog},
+	command::{ClientCommand, client::Response, error::CommandError},
+	output::{self, print_err},
+	profile::{self, Profile},
+	shard::HashRing,
+};
+
+pub const DEFAULT_NAME: &str = "default";
+
+const BACKOFF_INITIAL: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+pub struct Connection {
+	addr:   ServerAddr,
+	client: Option<PaperClient>,
+
+	auth_token: Option<String>,
+
+	/// Destructive commands are confirmed even if confirmation is skipped
+	/// for other connections.
+	always_confirm: bool,
+
+	/// Commands which change the cache are rejected.
+	read_only: bool,
+
+	/// Commands which change the cache are recorded in the log.
+	audit_log: Option<AuditLog>,
+
+	/// The responses received since they were last taken, if they are
+	/// being captured (e.g., to record the session).
+	responses: Option<Vec<Value>>,
+}
+
+impl Connection {
+	pub fn new(addr: ServerAddr, auth_token: Option<&str>) -> PaperClientResult<Self> {
+		let mut connection = Connection {
+			addr,
+			client: None,
+
+			auth_token: auth_token.map(ToOwned::to_owned),
+			always_confirm: false,
+			read_only: false,
+			audit_log: None,
+
+			responses: None,
+		};
+
+		connection.reconnect()?;
+
+		Ok(connection)
+	}
+
+	/// Creates a connection which has not yet been opened; commands fail
+	/// until it is reconnected.
+	pub fn unconnected(addr: ServerAddr) -> Self {
+		Connection {
+			addr,
+			client: None,
+
+			auth_token: None,
+			always_confirm: false,
+			read_only: false,
+			audit_log: None,
+
+			responses: None,
+		}
+	}
+
+	pub fn from_profile(profile: &Profile) -> Result<Self, CommandError> {
+		let mut connection = Connection::new(profile.addr()?, profile.auth())?;
+		connection.always_confirm = profile.always_confirm();
+		connection.read_only = profile.is_read_only();
+
+		connection.audit_log = profile
+			.audit_log()
+			.map(AuditLog::open)
+			.transpose()?;
+
+		Ok(connection)
+	}
+
+	/// Opens a connection to the supplied target, which is either the name
+	/// of a profile or an address of the form `[paper://]host[:port]`.
+	pub fn open(target: &str) -> Result<Self, CommandError> {
+		if let Some(profile) = profile::find(target)? {
+			return Connection::from_profile(&profile);
+		}
+
+		let addr = ServerAddr::from_str(target)?;
+
+		Ok(Connection::new(addr, None)?)
+	}
+
+	pub fn addr(&self) -> &ServerAddr {
+		&self.addr
+	}
+
+	pub fn always_confirm(&self) -> bool {
+		self.always_confirm
+	}
+
+	pub fn is_read_only(&self) -> bool {
+		self.read_only
+	}
+
+	pub fn set_read_only(&mut self) {
+		self.read_only = true;
+	}
+
+	pub fn is_connected(&self) -> bool {
+		self.client.is_some()
+	}
+
+	pub fn client(&mut self) -> Result<&mut PaperClient, CommandError> {
+		self.client
+			.as_mut()
+			.ok_or(CommandError::NotConnected)
+	}
+
+	/// Sends the command to the server. If the command changes the cache,
+	/// it is recorded in the audit log (if any) along with its result.
+	pub fn send(&mut self, command: ClientCommand) -> Result<Response, CommandError> {
+		let entries = match &self.audit_log {
+			Some(_) => audit::entries(&command),
+			None => Vec::new(),
+		};
+
+		let view = command.view();
+		// an attempt on a disconnected connection is recorded as well
+		let result = self.client().and_then(|client| command.send(client));
+
+		if let Some(responses) = &mut self.responses {
+			responses.push(output::result_json(&self.addr.to_string(), &result, &view));
+		}
+
+		if let Some(audit_log) = &mut self.audit_log
+			&& !entries.is_empty()
+			&& let Err(err) = audit_log.record(&self.addr.to_string(), entries, &result)
+		{
+			print_err(&err.to_string());
+		}
+
+		result
+	}
+
+	/// Starts or stops capturing the responses to the commands which are
+	/// sent, discarding any which were not taken.
+	pub fn capture_responses(&mut self, capture: bool) {
+		self.responses = capture.then(Vec::new);
+	}
+
+	pub fn take_responses(&mut self) -> Vec<Value> {
+		self.responses
+			.as_mut()
+			.map(mem::take)
+			.unwrap_or_default()
+	}
+
+	pub fn set_auth_token(&mut self, token: String) {
+		self.auth_token = Some(token);
+	}
+
+	/// Opens a new client to the connection's address, replacing the
+	/// existing one, and re-applies the last successful auth token.
+	pub fn reconnect(&mut self) -> PaperClientResult<()> {
+		let mut client = PaperClient::new(self.addr.url())?;
+
+		if let Some(token) = &self.auth_token {
+			client.auth(token)?;
+		}
+
+		self.client = Some(client);
+
+		Ok(())
+	}
+
+	pub fn disconnect(&mut self) {
+		self.client = None;
+	}
+}
+
+/// A set of named connections, one of which is active. The mode determines
+/// which connections receive commands that are not explicitly targeted at a
+/// connection.
+pub struct Connections {
+	entries: Vec<(String, Connection)>,
+	active:  usize,
+
+	mode: Mode,
+}
+
+pub enum Mode {
+	/// Commands are sent to the active connection.
+	Single,
+
+	/// Commands are sent to every connection.
+	FanOut,
+
+	/// Key commands are sent to the connection which owns the key and all
+	/// other commands are sent to every connection.
+	Sharded(HashRing),
+}
+
+pub enum Route {
+	Active,
+	All,
+	Node(String),
+
+	/// The keys of the command are owned by different connections, so the
+	/// command is split into one part for each of them.
+	Split(Vec<(String, ClientCommand)>),
+}
+
+impl Connections {
+	pub fn new(name: &str, connection: Connection) -> Self {
+		Connections {
+			entries: vec![(name.to_owned(), connection)],
+			active:  0,
+
+			mode: Mode::Single,
+		}
+	}
+
+	pub fn mode(&self) -> &Mode {
+		&self.mode
+	}
+
+	pub fn set_mode(&mut self, mode: Mode) {
+		self.mode = mode;
+	}
+
+	/// Returns the connection(s) which should receive the command.
+	pub fn route(&self, command: &ClientCommand) -> Result<Route, CommandError> {
+		match &self.mode {
+			Mode::Single => Ok(Route::Active),
+			Mode::FanOut => Ok(Route::All),
+
+			Mode::Sharded(ring) => {
+				let nodes = command
+					.keys()
+					.into_iter()
+					.map(|key| shard(ring, key))
+					.collect::<Result<Vec<_>, _>>()?;
+
+				match nodes.split_first() {
+					None => Ok(Route::All),
+
+					Some((node, rest)) if rest.iter().all(|other| other == node) => {
+						Ok(Route::Node((*node).to_owned()))
+					},
+
+					Some(_) => {
+						let parts = command.partition(|key| shard(ring, key).map(ToOwned::to_owned))?;
+						Ok(Route::Split(parts))
+					},
+				}
+			},
+		}
+	}
+
+	/// Returns the name of the connection which owns the key in sharded mode.
+	pub fn shard(&self, key: &str) -> Result<&str, CommandError> {
+		match &self.mode {
+			Mode::Sharded(ring) => shard(ring, key),
+			_ => Err(CommandError::NotSharded),
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn capture_responses(&mut self, capture: bool) {
+		for (_, connection) in &mut self.entries {
+			connection.capture_responses(capture);
+		}
+	}
+
+	/// Takes the captured responses of every connection.
+	pub fn take_responses(&mut self) -> Vec<Value> {
+		self.entries
+			.iter_mut()
+			.flat_map(|(_, connection)| connection.take_responses())
+			.collect()
+	}
+
+	pub fn active_name(&self) -> &str {
+		&self.entries[self.active].0
+	}
+
+	pub fn active(&mut self) -> &mut Connection {
+		&mut self.entries[self.active].1
+	}
+
+	pub fn get(&mut self, name: &str) -> Result<&mut Connection, CommandError> {
+		let index = self.index(name)?;
+		Ok(&mut self.entries[index].1)
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &Connection)> {
+		self.entries
+			.iter()
+			.map(|(name, connection)| (name.as_str(), connection))
+	}
+
+	pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut Connection)> {
+		self.entries
+			.iter_mut()
+			.map(|(name, connection)| (name.as_str(), connection))
+	}
+
+	/// Adds the connection under the supplied name, replacing any existing
+	/// connection with that name, and makes it the active connection.
+	pub fn open(&mut self, name: &str, connection: Connection) {
+		match self.index(name) {
+			Ok(index) => {
+				self.entries[index].1 = connection;
+				self.active = index;
+			},
+
+			Err(_) => {
+				self.entries.push((name.to_owned(), connection));
+				self.active = self.entries.len() - 1;
+			},
+		}
+	}
+
+	pub fn select(&mut self, name: &str) -> Result<(), CommandError> {
+		self.active = self.index(name)?;
+		Ok(())
+	}
+
+	/// Removes the connection with the supplied name. If it was the active
+	/// connection, the first remaining connection becomes active.
+	pub fn close(&mut self, name: &str) -> Result<(), CommandError> {
+		let index = self.index(name)?;
+
+		if self.entries.len() == 1 {
+			return Err(CommandError::LastConnection);
+		}
+
+		self.entries.remove(index);
+
+		if self.active == index {
+			self.active = 0;
+		} else if self.active > index {
+			self.active -= 1;
+		}
+
+		Ok(())
+	}
+
+	fn index(&self, name: &str) -> Result<usize, CommandError> {
+		self.entries
+			.iter()
+			.position(|(entry_name, _)| entry_name == name)
+			.ok_or(CommandError::UnknownConnection)
+	}
+}
+
+fn shard<'a>(ring: &'a HashRing, key: &str) -> Result<&'a str, CommandError> {
+	ring.node(key).ok_or(CommandError::NoConnections)
+}
+
+/// Returns the delay before the supplied (zero-indexed) reconnect attempt,
+/// doubling with each attempt up to a fixed maximum.
+pub fn backoff(attempt: u32) -> Duration {
+	BACKOFF_INITIAL
+		.checked_mul(2u32.saturating_pow(attempt))
+		.map_or(BACKOFF_MAX, |delay| delay.min(BACKOFF_MAX))
+}
+
+pub fn is_disconnect(err: &PaperClientError) -> bool {
+	matches!(
+		err,
+		PaperClientError::Disconnected | PaperClientError::UnreachableServer
+	)
+}
@@ -0,0 +1,110 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	env,
+	fs::{self, File, OpenOptions},
+	io::{ErrorKind, Write},
+	path::PathBuf,
+	process,
+};
+
+use kwik::time;
+
+use crate::{command::error::CommandError, line_reader};
+
+const DEFAULT_EDITOR: &str = "vi";
+
+/// The number of names tried for the temporary file before giving up.
+const MAX_TEMP_FILE_ATTEMPTS: usize = 10;
+
+/// Opens the value in the user's editor and returns the edited value, or
+/// `None` if it was not changed.
+pub fn edit(buf: &[u8]) -> Result<Option<Vec<u8>>, CommandError> {
+	if str::from_utf8(buf).is_err() {
+		return Err(CommandError::BinaryEdit);
+	}
+
+	let (path, mut file) = create_temp_file()?;
+
+	let written = file.write_all(buf);
+	drop(file);
+
+	if written.is_err() {
+		let _ = fs::remove_file(&path);
+		return Err(CommandError::InvalidTempFile);
+	}
+
+	let result = line_reader::without_raw_mode(|| {
+		let status = process::Command::new("sh")
+			.arg("-c")
+			.arg(format!("{} \"$1\"", editor()))
+			.arg("sh")
+			.arg(&path)
+			.status();
+
+		match status {
+			Ok(status) if status.success() => {
+				fs::read(&path).map_err(|_| CommandError::InvalidTempFile)
+			},
+
+			_ => Err(CommandError::EditorFailed),
+		}
+	});
+
+	let _ = fs::remove_file(&path);
+
+	let mut edited = result?;
+
+	// most editors end the file with a new line even if the value did not
+	if !buf.ends_with(b"\n") && edited.ends_with(b"\n") {
+		edited.pop();
+	}
+
+	match edited == buf {
+		true => Ok(None),
+		false => Ok(Some(edited)),
+	}
+}
+
+/// Creates a new file in the temporary directory which only the user can
+/// read and write. An existing file (e.g., a symlink created by another user
+/// with the same name) is never opened.
+fn create_temp_file() -> Result<(PathBuf, File), CommandError> {
+	for attempt in 0..MAX_TEMP_FILE_ATTEMPTS {
+		let path = env::temp_dir().join(format!(
+			"paper-cli-{}-{}-{attempt}",
+			process::id(),
+			time::timestamp(),
+		));
+
+		let mut options = OpenOptions::new();
+		options.write(true).create_new(true);
+
+		#[cfg(unix)]
+		{
+			use std::os::unix::fs::OpenOptionsExt;
+			options.mode(0o600);
+		}
+
+		match options.open(&path) {
+			Ok(file) => return Ok((path, file)),
+			Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+			Err(_) => break,
+		}
+	}
+
+	Err(CommandError::InvalidTempFile)
+}
+
+fn editor() -> String {
+	env::var("VISUAL")
+		.or_else(|_| env::var("EDITOR"))
+		.ok()
+		.filter(|editor| !editor.trim().is_empty())
+		.unwrap_or_else(|| DEFAULT_EDITOR.into())
+}
@@ -0,0 +1,130 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::time::{Duration, Instant};
+
+use paper_client::Status;
+
+use crate::{
+	command::{ClientCommand, client::Response, error::CommandError},
+	connection::Connections,
+};
+
+pub struct HostResult {
+	host:    String,
+	result:  Result<Response, CommandError>,
+	elapsed: Duration,
+}
+
+/// The sum of the status fields of several hosts, with the miss ratio of
+/// each host weighted by the number of gets it served.
+pub struct StatusTotals {
+	num_hosts: usize,
+
+	max_size:    u64,
+	used_size:   u64,
+	num_objects: u64,
+	total_gets:  u64,
+	miss_ratio:  f64,
+}
+
+impl HostResult {
+	pub fn host(&self) -> &str {
+		&self.host
+	}
+
+	pub fn result(&self) -> Result<&Response, &CommandError> {
+		self.result.as_ref()
+	}
+
+	pub fn elapsed(&self) -> Duration {
+		self.elapsed
+	}
+}
+
+impl StatusTotals {
+	pub fn new(statuses: &[&Status]) -> Self {
+		let total_gets = statuses
+			.iter()
+			.map(|status| status.total_gets())
+			.sum::<u64>();
+
+		let miss_ratio = match total_gets {
+			0 => 0.0,
+
+			_ => {
+				let total_misses = statuses
+					.iter()
+					.map(|status| status.miss_ratio() * status.total_gets() as f64)
+					.sum::<f64>();
+
+				total_misses / total_gets as f64
+			},
+		};
+
+		StatusTotals {
+			num_hosts: statuses.len(),
+
+			max_size: statuses.iter().map(|status| status.max_size()).sum(),
+			used_size: statuses.iter().map(|status| status.used_size()).sum(),
+			num_objects: statuses.iter().map(|status| status.num_objects()).sum(),
+			total_gets,
+			miss_ratio,
+		}
+	}
+
+	pub fn num_hosts(&self) -> usize {
+		self.num_hosts
+	}
+
+	pub fn max_size(&self) -> u64 {
+		self.max_size
+	}
+
+	pub fn used_size(&self) -> u64 {
+		self.used_size
+	}
+
+	pub fn num_objects(&self) -> u64 {
+		self.num_objects
+	}
+
+	pub fn total_gets(&self) -> u64 {
+		self.total_gets
+	}
+
+	pub fn miss_ratio(&self) -> f64 {
+		self.miss_ratio
+	}
+}
+
+/// Sends the command to every connection and returns one result per host.
+/// A failure on one host does not prevent the command from being sent to
+/// the others.
+pub fn send(command: &ClientCommand, connections: &mut Connections) -> Vec<HostResult> {
+	let mut results = Vec::with_capacity(connections.len());
+
+	for (name, connection) in connections.iter_mut() {
+		let time = Instant::now();
+
+		let result = connection.send(command.clone());
+
+		let elapsed = time.elapsed();
+
+		if let (Ok(_), ClientCommand::Auth(token)) = (&result, command) {
+			connection.set_auth_token(token.clone());
+		}
+
+		results.push(HostResult {
+			host: name.to_owned(),
+			result,
+			elapsed,
+		});
+	}
+
+	results
+}
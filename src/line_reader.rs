@@ -12,7 +12,7 @@ mod line;
 
 use std::{
 	io,
-	io::{Stdout, Write},
+	io::{IsTerminal, Stdout, Write},
 };
 
 use crossterm::{
@@ -61,6 +61,10 @@ impl LineReader {
 		}
 	}
 
+	pub fn set_prompt(&mut self, prompt: String) {
+		self.prompt = prompt;
+	}
+
 	pub fn hints(&self) -> &[&'static str] {
 		self.hinter.hints()
 	}
@@ -152,6 +156,63 @@ impl LineReader {
 	}
 }
 
+/// Asks the user to confirm an action by pressing `y`, which is refused if
+/// any other key is pressed or if stdin is not a terminal. The prompt is
+/// shown either way, so that the user knows what was refused.
+pub fn confirm(prompt: &str) -> Result<bool, LineReaderError> {
+	let mut stdout = io::stdout();
+
+	if !io::stdin().is_terminal() {
+		writeln!(stdout, "{prompt} [y/N] n (pass --yes to confirm without a terminal)")
+			.map_err(|_| LineReaderError::Internal)?;
+
+		return Ok(false);
+	}
+
+	write!(stdout, "{prompt} [y/N] ").map_err(|_| LineReaderError::Internal)?;
+	flush(&mut stdout)?;
+
+	enable_raw_mode()?;
+
+	let is_confirmed = loop {
+		match event() {
+			ReadEvent::Character('y' | 'Y') => break true,
+			ReadEvent::Skip => continue,
+			_ => break false,
+		}
+	};
+
+	let answer = match is_confirmed {
+		true => "y",
+		false => "n",
+	};
+
+	let write_result = write!(stdout, "{answer}").map_err(|_| LineReaderError::Internal);
+
+	clear(&mut stdout)?;
+	disable_raw_mode()?;
+
+	write_result.map(|_| is_confirmed)
+}
+
+/// Runs the function with raw mode disabled so that another program can
+/// use the terminal, and re-enables raw mode afterwards if it was enabled.
+pub fn without_raw_mode<T>(f: impl FnOnce() -> T) -> T {
+	let is_raw_mode = terminal::is_raw_mode_enabled().unwrap_or(false);
+
+	if is_raw_mode {
+		let _ = disable_raw_mode();
+	}
+
+	let result = f();
+
+	if is_raw_mode {
+		let _ = enable_raw_mode();
+	}
+
+	result
+}
+
 fn event() -> ReadEvent {
 	let crossterm_event = match crossterm_read() {
 		Ok(event) => event,
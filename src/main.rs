@@ -5,163 +5,1334 @@
  * LICENSE file in the root directory of this source tree.
  */
 
+mod addr;
+mod audit;
 mod command;
+mod connection;
+mod editor;
+mod fanout;
 mod line_reader;
+mod output;
+mod picker;
+mod profile;
+mod record;
+mod shard;
 
 use std::{
+	fs,
+	io::{self, Write},
+	path::{Path, PathBuf},
+	process::ExitCode,
+	str::FromStr,
 	thread,
 	time::{Duration, Instant},
 };
 
-use clap::Parser;
-use paper_client::{PaperClient, PaperClientError};
+use clap::{Parser, Subcommand};
+use kwik::fmt;
+use paper_client::{PaperClient, Status};
 
-use crate::command::{
-	CliCommand,
-	ClientCommand,
-	Command,
-	error::CommandError,
-	parser::CommandParser,
+use crate::{
+	addr::ServerAddr,
+	command::{
+		CliCommand,
+		ClientCommand,
+		Command,
+		client::Response,
+		error::CommandError,
+		multi::{KeyResult, KeyResults},
+		parser::CommandParser,
+		resize::Resize,
+		view::View,
+	},
+	connection::{Connection, Connections, Mode, Route},
+	output::{Format, print_err, print_note, print_ok, print_warning},
+	record::{Entry, Recorder},
+	shard::HashRing,
 };
 
+/// The number of keys an expanded command can operate on before it must be
+/// confirmed.
+const EXPANSION_CONFIRM_THRESHOLD: usize = 100;
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+	/// Server URL of the form paper://host:port (alternative to --host/--port)
+	#[arg(conflicts_with_all = ["host", "port", "profile"])]
+	url: Option<String>,
+
 	#[arg(long, default_value = "127.0.0.1")]
 	host: String,
 
 	#[arg(long, default_value_t = 3145)]
-	port: u32,
+	port: u16,
+
+	/// Connect using a profile from the profiles file
+	#[arg(long, conflicts_with_all = ["host", "port"])]
+	profile: Option<String>,
+
+	/// Send every command to all of the supplied servers
+	#[arg(long, value_delimiter = ',', conflicts_with_all = ["url", "host", "port", "profile"])]
+	hosts: Vec<String>,
+
+	/// Send every command to all of the servers listed in the file (one per line)
+	#[arg(long, conflicts_with_all = ["url", "host", "port", "profile"])]
+	hosts_file: Option<PathBuf>,
+
+	/// Route key commands to one of the supplied servers by consistent hashing
+	#[arg(
+		long,
+		value_delimiter = ',',
+		conflicts_with_all = ["url", "host", "port", "profile", "hosts", "hosts_file"],
+	)]
+	shards: Vec<String>,
+
+	/// Number of reconnect attempts after the connection is lost
+	#[arg(long, default_value_t = 10)]
+	reconnect_attempts: u32,
+
+	/// Re-send the command that failed once the connection is restored
+	#[arg(long)]
+	retry_failed: bool,
+
+	/// Format of command responses
+	#[arg(long, value_enum, default_value_t = Format::Text)]
+	output: Format,
+
+	/// Run the command and exit instead of starting an interactive session
+	/// (can be repeated)
+	#[arg(short, long = "command")]
+	commands: Vec<String>,
+
+	/// Reject commands which change the cache (e.g., set, del or wipe)
+	#[arg(long)]
+	read_only: bool,
+
+	/// Print the requests each command would send instead of sending them
+	#[arg(long)]
+	dry_run: bool,
+
+	/// Do not ask for confirmation before wiping, shrinking or changing the
+	/// policy of a cache (unless its profile always asks)
+	#[arg(short, long)]
+	yes: bool,
+
+	/// Record every input line of the session, along with its responses,
+	/// timing and any error, to the file
+	#[arg(long, value_name = "PATH")]
+	record: Option<PathBuf>,
+
+	#[command(subcommand)]
+	action: Option<Action>,
+}
+
+#[derive(Subcommand)]
+enum Action {
+	/// Re-run the input lines of a recorded session and highlight the
+	/// responses which differ from the recording
+	Replay {
+		path: PathBuf,
+	},
 }
 
-fn main() {
+fn main() -> ExitCode {
 	let args = Args::parse();
-	let addr = format!("paper://{}:{}", args.host, args.port);
 
-	loop {
-		let mut client = match PaperClient::new(&addr) {
-			Ok(client) => client,
+	let mut connections = match open_initial_connections(&args) {
+		Ok(connections) => connections,
+
+		Err(err) => {
+			print_err(&err.to_string());
+			return ExitCode::FAILURE;
+		},
+	};
+
+	let mut parser = CommandParser::new(connections.active().addr());
+
+	if args.read_only {
+		parser.set_read_only();
+	}
+
+	if args.dry_run {
+		parser.set_dry_run();
+	}
+
+	update_read_only(&mut connections, &mut parser);
+	update_prompt(&mut connections, &mut parser);
+
+	if let Some(Action::Replay { path }) = &args.action {
+		return replay(path, &mut connections, &mut parser, &args);
+	}
+
+	let mut recorder = match args.record.as_deref().map(Recorder::open).transpose() {
+		Ok(recorder) => recorder,
+
+		Err(err) => {
+			print_err(&err.to_string());
+			return ExitCode::FAILURE;
+		},
+	};
+
+	if !args.commands.is_empty() {
+		return run_commands(&mut connections, &mut parser, &args, &mut recorder);
+	}
+
+	while parser.reading() {
+		let line = match parser.read_line() {
+			Ok(line) => line,
+
+			Err(err) if err == CommandError::Interrupted => {
+				print_note(&err.to_string());
+				return ExitCode::SUCCESS;
+			},
 
 			Err(err) => {
 				print_err(&err.to_string());
-				return;
+				continue;
 			},
 		};
 
-		let mut parser = CommandParser::new(&args.host, args.port);
+		match handle_line(&line, &mut connections, &mut parser, &args, &mut recorder) {
+			Ok(Ok(_)) => {},
+
+			Ok(Err(err)) if err == CommandError::Interrupted => {
+				print_note(&err.to_string());
+				return ExitCode::SUCCESS;
+			},
+
+			// the line could not be parsed or the command failed
+			Ok(Err(err)) | Err(err) => print_failure(&err),
+		}
 
-		while parser.reading() {
-			match parser.read() {
-				Ok(command) => match handle_command(command, &mut client, &mut parser) {
-					Ok(_) => {},
+		// the connection may have been lost
+		update_prompt(&mut connections, &mut parser);
+	}
 
-					Err(err) if err == CommandError::InvalidResponse => {
-						print_err(&err.to_string());
-					},
+	ExitCode::SUCCESS
+}
 
-					Err(err) if err == CommandError::Interrupted => {
-						print_note(&err.to_string());
-						return;
-					},
+/// Runs the commands supplied with `--command` in order, stopping at the
+/// first one which cannot be parsed or fails. Returns a failure exit code
+/// if any command failed.
+fn run_commands(
+	connections: &mut Connections,
+	parser: &mut CommandParser,
+	args: &Args,
+	recorder: &mut Option<Recorder>,
+) -> ExitCode {
+	for line in &args.commands {
+		match handle_line(line, connections, parser, args, recorder) {
+			Ok(Ok(_)) => {},
+			Ok(Err(CommandError::Interrupted)) => return ExitCode::SUCCESS,
 
-					Err(err) => {
-						print_err(&err.to_string());
-						return;
-					},
-				},
+			Ok(Err(err)) | Err(err) => {
+				print_failure(&err);
+				return ExitCode::FAILURE;
+			},
+		}
+	}
+
+	ExitCode::SUCCESS
+}
 
-				Err(err) if err == CommandError::Interrupted => {
-					print_note(&err.to_string());
-					return;
-				},
+/// Returns `true` if a replayed session cannot continue after the error.
+fn is_fatal(err: &CommandError) -> bool {
+	matches!(err, CommandError::Interrupted | CommandError::Disconnected)
+}
 
-				Err(err) => print_err(&err.to_string()),
+/// Prints the error a command failed with, unless it was already printed
+/// along with the command's response.
+fn print_failure(err: &CommandError) {
+	if !matches!(err, CommandError::CommandFailed(_)) {
+		print_err(&err.to_string());
+	}
+}
+
+/// Prints the error a command failed with, and returns the error which
+/// reports that it was already printed.
+fn report_error(message: &str, args: &Args) -> CommandError {
+	output::print_error(args.output, message);
+	CommandError::CommandFailed(message.to_owned())
+}
+
+/// Parses and runs a line of input. If the session is being recorded, the
+/// line is recorded along with the responses it received and any error.
+/// Returns the error the line could not be parsed with, or the result of
+/// running it.
+fn handle_line(
+	line: &str,
+	connections: &mut Connections,
+	parser: &mut CommandParser,
+	args: &Args,
+	recorder: &mut Option<Recorder>,
+) -> Result<Result<(), CommandError>, CommandError> {
+	let time = Instant::now();
+
+	connections.capture_responses(recorder.is_some());
+
+	let command = parser.parse(line);
+	let input = record::input(line, command.as_ref().ok());
+
+	let result = command.map(|command| handle_command(command, connections, parser, args, recorder));
+
+	if let (Some(recorder), Some(input)) = (recorder, input) {
+		let err = match &result {
+			Ok(result) => result.as_ref().err(),
+			Err(err) => Some(err),
+		};
+
+		let entry = Entry::new(input, time.elapsed(), connections.take_responses(), err);
+
+		if let Err(err) = recorder.record(&entry) {
+			print_err(&err.to_string());
+		}
+	}
+
+	result
+}
+
+/// Runs the input lines of a recorded session in order, and prints the
+/// responses which differ from the recorded ones. Fails if any of them
+/// differ.
+fn replay(
+	path: &Path,
+	connections: &mut Connections,
+	parser: &mut CommandParser,
+	args: &Args,
+) -> ExitCode {
+	let entries = match record::read(path) {
+		Ok(entries) => entries,
+
+		Err(err) => {
+			print_err(&err.to_string());
+			return ExitCode::FAILURE;
+		},
+	};
+
+	let mut num_replayed: usize = 0;
+	let mut num_differences: usize = 0;
+
+	for expected in entries {
+		let time = Instant::now();
+
+		connections.capture_responses(true);
+
+		if args.output.is_text() {
+			println!("\x1B[1m> {}\x1B[0m", expected.input());
+		}
+
+		let result = match parser.parse(expected.input()) {
+			Ok(command) if record::is_redacted(&command) => {
+				print_note("Skipping auth, which was recorded without its token.");
+				continue;
+			},
+
+			Ok(command) => {
+				let result = handle_command(command, connections, parser, args, &mut None);
+
+				if let Err(err) = &result
+					&& !is_fatal(err)
+				{
+					print_failure(err);
+				}
+
+				Ok(result)
+			},
+
+			Err(err) => {
+				output::print_error(args.output, &err.to_string());
+				Err(err)
+			},
+		};
+
+		let err = match &result {
+			Ok(result) => result.as_ref().err(),
+			Err(err) => Some(err),
+		};
+
+		let actual = Entry::new(
+			expected.input().to_owned(),
+			time.elapsed(),
+			connections.take_responses(),
+			err,
+		);
+
+		num_replayed += 1;
+
+		if !actual.matches(&expected) {
+			num_differences += 1;
+			output::print_replay_difference(args.output, &expected, &actual);
+		}
+
+		// the replay cannot continue once the connection is lost or the
+		// recorded session quit
+		if let Ok(Err(err)) = result
+			&& is_fatal(&err)
+		{
+			if err != CommandError::Interrupted {
+				print_err(&err.to_string());
 			}
+
+			break;
+		}
+	}
+
+	if args.output.is_text() {
+		match num_differences {
+			0 => print_ok(&format!("replayed {num_replayed} lines, all responses match the recording")),
+
+			_ => print_warning(&format!(
+				"replayed {num_replayed} lines, the responses to {num_differences} differ from the recording",
+			)),
 		}
 	}
+
+	match num_differences {
+		0 => ExitCode::SUCCESS,
+		_ => ExitCode::FAILURE,
+	}
+}
+
+impl Args {
+	fn fan_out_hosts(&self) -> Result<Vec<String>, CommandError> {
+		let mut hosts = self.hosts.clone();
+
+		if let Some(path) = &self.hosts_file {
+			let data = fs::read_to_string(path).map_err(|_| CommandError::InvalidHostsFile)?;
+
+			let file_hosts = data
+				.lines()
+				.map(str::trim)
+				.filter(|line| !line.is_empty() && !line.starts_with('#'))
+				.map(ToOwned::to_owned);
+
+			hosts.extend(file_hosts);
+		}
+
+		Ok(hosts)
+	}
+}
+
+fn open_initial_connections(args: &Args) -> Result<Connections, CommandError> {
+	if !args.shards.is_empty() {
+		let mut connections = open_group_connections(&args.shards)?;
+
+		let names = connections
+			.iter()
+			.map(|(name, _)| name)
+			.collect::<Vec<_>>();
+
+		let ring = HashRing::new(names);
+		connections.set_mode(Mode::Sharded(ring));
+
+		return Ok(connections);
+	}
+
+	let fan_out_hosts = args.fan_out_hosts()?;
+
+	if !fan_out_hosts.is_empty() {
+		let mut connections = open_group_connections(&fan_out_hosts)?;
+		connections.set_mode(Mode::FanOut);
+
+		return Ok(connections);
+	}
+
+	let connection = match &args.profile {
+		Some(name) => {
+			let profile = profile::find(name)?.ok_or(CommandError::UnknownProfile)?;
+			Connection::from_profile(&profile)?
+		},
+
+		None => {
+			let addr = match &args.url {
+				Some(url) => ServerAddr::from_str(url)?,
+				None => ServerAddr::new(&args.host, args.port)?,
+			};
+
+			Connection::new(addr, None)?
+		},
+	};
+
+	Ok(Connections::new(connection::DEFAULT_NAME, connection))
+}
+
+/// Opens a connection to each host, named by its address. Hosts which
+/// cannot be reached are reported and kept as unconnected so they can be
+/// reconnected later.
+fn open_group_connections(hosts: &[String]) -> Result<Connections, CommandError> {
+	let mut connections: Option<Connections> = None;
+	let mut num_connected: usize = 0;
+
+	for host in hosts {
+		let addr = ServerAddr::from_str(host)?;
+
+		let connection = match Connection::new(addr.clone(), None) {
+			Ok(connection) => {
+				num_connected += 1;
+				connection
+			},
+
+			Err(err) => {
+				print_err(&format!("{addr}: {err}"));
+				Connection::unconnected(addr)
+			},
+		};
+
+		let name = connection.addr().to_string();
+
+		match &mut connections {
+			Some(connections) => connections.open(&name, connection),
+			None => connections = Some(Connections::new(&name, connection)),
+		}
+	}
+
+	let mut connections = connections.ok_or(CommandError::NoConnections)?;
+
+	if num_connected == 0 {
+		return Err(CommandError::NoConnections);
+	}
+
+	let first_name = connections
+		.iter()
+		.map(|(name, _)| name.to_owned())
+		.next();
+
+	if let Some(name) = first_name {
+		connections.select(&name)?;
+	}
+
+	Ok(connections)
 }
 
 fn handle_command(
 	command: Command,
-	client: &mut PaperClient,
+	connections: &mut Connections,
 	parser: &mut CommandParser,
+	args: &Args,
+	recorder: &mut Option<Recorder>,
 ) -> Result<(), CommandError> {
 	match command {
-		Command::Client(client_command) => handle_client_command(client_command, client),
+		Command::Client(client_command) => match connections.route(&client_command)? {
+			Route::Active => handle_client_command(client_command, connections.active(), args),
+			Route::All => handle_fan_out_command(client_command, connections, args),
+
+			Route::Node(name) => handle_named_command(&name, client_command, connections, args),
+			Route::Split(parts) => handle_split_command(&client_command, parts, connections, args),
+		},
 
-		Command::Cli(cli_command) => handle_cli_command(cli_command, parser),
+		Command::Targeted(name, client_command) => {
+			handle_named_command(&name, client_command, connections, args)
+		},
+
+		Command::Expanded(num_keys, commands) => {
+			handle_expanded_commands(num_keys, commands, connections, parser, args, recorder)
+		},
+
+		Command::Dry(command) => match dry_run_requests(*command, connections) {
+			Ok(requests) => {
+				output::print_dry_run(args.output, &requests);
+				Ok(())
+			},
+
+			Err(err) => Err(report_error(&err.to_string(), args)),
+		},
+
+		Command::Cli(cli_command) => handle_cli_command(cli_command, connections, parser, recorder),
 	}
 }
 
+/// Returns the requests the command would send, each with the host it
+/// would be sent to, checking the command the same way as if it were sent.
+fn dry_run_requests(
+	command: Command,
+	connections: &mut Connections,
+) -> Result<Vec<(String, String)>, CommandError> {
+	let (name, command) = match command {
+		Command::Client(command) => (None, command),
+		Command::Targeted(name, command) => (Some(name), command),
+
+		// several expanded commands are checked the way they are when sent
+		Command::Expanded(_, commands) if commands.len() > 1 => {
+			let mut requests = Vec::new();
+
+			for command in commands {
+				let (Command::Client(client_command) | Command::Targeted(_, client_command)) = &command
+				else {
+					continue;
+				};
+
+				if client_command.view().out().is_some() {
+					return Err(CommandError::GroupOutputFile);
+				}
+
+				if let Command::Client(client_command) = &command {
+					match connections.route(client_command)? {
+						Route::All => return Err(CommandError::GroupExpansion),
+
+						Route::Split(_) if !matches!(client_command, ClientCommand::Diff(..)) => {
+							return Err(CommandError::CrossShardKeys);
+						},
+
+						Route::Active | Route::Node(_) | Route::Split(_) => {},
+					}
+				}
+
+				requests.extend(dry_run_requests(command, connections)?);
+			}
+
+			return Ok(requests);
+		},
+
+		Command::Expanded(_, commands) => match commands.into_iter().next() {
+			Some(command) => return dry_run_requests(command, connections),
+			None => return Ok(Vec::new()),
+		},
+
+		Command::Dry(_) | Command::Cli(_) => return Err(CommandError::InvalidDryRun),
+	};
+
+	let route = match name {
+		Some(name) => Route::Node(name),
+		None => connections.route(&command)?,
+	};
+
+	let hosts = match route {
+		Route::Active => vec![(connections.active().addr().to_string(), command)],
+		Route::Node(name) => vec![(connections.get(&name)?.addr().to_string(), command)],
+
+		Route::All => {
+			if command.view().out().is_some() {
+				return Err(CommandError::GroupOutputFile);
+			}
+
+			if matches!(command, ClientCommand::Edit(..)) {
+				return Err(CommandError::GroupEdit);
+			}
+
+			connections
+				.iter()
+				.map(|(_, connection)| (connection.addr().to_string(), command.clone()))
+				.collect()
+		},
+
+		Route::Split(parts) => {
+			if command.action().is_none() && !matches!(command, ClientCommand::Diff(..)) {
+				return Err(CommandError::CrossShardKeys);
+			}
+
+			parts
+				.into_iter()
+				.map(|(name, part)| Ok((connections.get(&name)?.addr().to_string(), part)))
+				.collect::<Result<Vec<_>, CommandError>>()?
+		},
+	};
+
+	let requests = hosts
+		.into_iter()
+		.flat_map(|(host, command)| {
+			command
+				.requests()
+				.into_iter()
+				.map(move |request| (host.clone(), request))
+		})
+		.collect();
+
+	Ok(requests)
+}
+
+/// Runs the commands produced by expanding the arguments of one command,
+/// asking for confirmation first if they operate on many keys. Several
+/// commands are sent one after another and their results are printed
+/// together, with one row for each key.
+fn handle_expanded_commands(
+	num_keys: usize,
+	mut commands: Vec<Command>,
+	connections: &mut Connections,
+	parser: &mut CommandParser,
+	args: &Args,
+	recorder: &mut Option<Recorder>,
+) -> Result<(), CommandError> {
+	if num_keys > EXPANSION_CONFIRM_THRESHOLD {
+		let prompt = format!("Expand into {num_keys} keys?");
+
+		if !args.yes && !line_reader::confirm(&prompt).unwrap_or(false) {
+			print_note("Cancelled.");
+			return Err(CommandError::CommandFailed("cancelled".to_owned()));
+		}
+	}
+
+	if commands.len() == 1 {
+		return handle_command(commands.remove(0), connections, parser, args, recorder);
+	}
+
+	let mut results = KeyResults::new("done");
+	let mut view = View::default();
+
+	for command in commands {
+		let (name, command) = match command {
+			Command::Client(command) => (None, command),
+			Command::Targeted(name, command) => (Some(name), command),
+			_ => continue,
+		};
+
+		let key = command
+			.keys()
+			.first()
+			.map(|key| (*key).to_owned())
+			.unwrap_or_default();
+
+		if command.view().out().is_some() {
+			return Err(CommandError::GroupOutputFile);
+		}
+
+		view = command.view();
+
+		let result = send_expanded_command(name, command, connections);
+
+		if matches!(result, Err(CommandError::GroupExpansion)) {
+			return Err(CommandError::GroupExpansion);
+		}
+
+		results.push(KeyResult::new(key, result));
+	}
+
+	print_response(&Response::Keys(results), &view, None, false, args)
+}
+
+/// Sends one of several expanded commands to the connection it is targeted
+/// at, or to the connection it would be routed to otherwise.
+fn send_expanded_command(
+	name: Option<String>,
+	command: ClientCommand,
+	connections: &mut Connections,
+) -> Result<Response, CommandError> {
+	let name = match name {
+		Some(name) => Some(name),
+
+		None => match connections.route(&command)? {
+			Route::Active => None,
+			Route::Node(name) => Some(name),
+			Route::All => return Err(CommandError::GroupExpansion),
+
+			Route::Split(parts) if matches!(command, ClientCommand::Diff(..)) => {
+				return send_split_diff(parts, connections);
+			},
+
+			Route::Split(_) => return Err(CommandError::CrossShardKeys),
+		},
+	};
+
+	let connection = match name {
+		Some(name) => connections.get(&name)?,
+		None => connections.active(),
+	};
+
+	connection.send(command)
+}
+
+fn handle_named_command(
+	name: &str,
+	command: ClientCommand,
+	connections: &mut Connections,
+	args: &Args,
+) -> Result<(), CommandError> {
+	let connection = connections.get(name)?;
+	handle_client_command(command, connection, args)
+}
+
 fn handle_client_command(
 	command: ClientCommand,
-	client: &mut PaperClient,
+	connection: &mut Connection,
+	args: &Args,
 ) -> Result<(), CommandError> {
 	let time = Instant::now();
 
 	let is_ping = matches!(command, ClientCommand::Ping);
 	let is_status_watch = matches!(command, ClientCommand::Status(true));
 
-	match command.send(client) {
-		Ok(buf) => {
-			let mut message: String = buf
-				.try_into()
-				.map_err(|_| CommandError::InvalidResponse)?;
+	let auth_token = match &command {
+		ClientCommand::Auth(token) => Some(token.clone()),
+		_ => None,
+	};
+
+	let retry_command = args.retry_failed.then(|| command.clone());
+	let view = command.view();
+
+	let edit = match &command {
+		ClientCommand::Edit(key, ttl) => Some((key.clone(), *ttl)),
+		_ => None,
+	};
+
+	// commands are checked when they are parsed, but some are created by the
+	// CLI itself (e.g., the policy picked from a list)
+	if command.is_mutating() && connection.is_read_only() {
+		return Err(CommandError::ReadOnly);
+	}
+
+	let is_policy_pick = matches!(command, ClientCommand::Policy(None))
+		&& args.output.is_text()
+		&& !connection.is_read_only()
+		&& picker::is_available();
 
-			if is_ping {
-				message += &format!(" ({:?})", time.elapsed());
+	let host = connection.addr().to_string();
+	let is_confirmed = args.yes && !connection.always_confirm();
+
+	// a command on a disconnected connection is still sent, so that it
+	// fails the way it does on a connected one and is audited
+	let summary = match connection.is_connected() {
+		true => connection
+			.client()
+			.and_then(|client| change_summary(&command, &host, client, args.output)),
+
+		false => Ok(None),
+	};
+
+	let result = match summary {
+		Ok(Some(summary)) if !is_confirmed => match line_reader::confirm(&format!("{summary}?")) {
+			Ok(true) => connection.send(command),
+
+			_ => {
+				print_note("Cancelled.");
+				return Err(CommandError::CommandFailed("cancelled".to_owned()));
+			},
+		},
+
+		Ok(_) => connection.send(command),
+		Err(err) => Err(err),
+	};
+
+	match result {
+		Ok(response) => {
+			if let Some(token) = auth_token {
+				connection.set_auth_token(token);
 			}
 
-			if is_status_watch {
+			if let (Some((key, ttl)), Response::Bytes(value)) = (edit, &response) {
+				return handle_edit(key, value.into(), ttl, connection, args);
+			}
+
+			let elapsed = is_ping.then(|| time.elapsed());
+
+			if is_status_watch && args.output.is_text() {
 				CliCommand::Clear.run()?;
 			}
 
-			print_ok(&message);
+			print_response(&response, &view, elapsed, is_status_watch, args)?;
+
+			if let (true, Response::Policies(status)) = (is_policy_pick, &response) {
+				return handle_policy_pick(status, connection, args);
+			}
 
 			if is_status_watch {
-				print_note("Watching cache status... Ctrl-C to exit.");
+				if args.output.is_text() {
+					print_note("Watching cache status... Ctrl-C to exit.");
+				}
+
 				thread::sleep(Duration::from_secs(1));
-				return handle_client_command(ClientCommand::Status(true), client);
+				return handle_client_command(ClientCommand::Status(true), connection, args);
 			}
 		},
 
-		Err(err) if err == PaperClientError::Disconnected => {
+		Err(CommandError::Client(err)) if connection::is_disconnect(&err) => {
 			print_err(&err.to_string());
-			return Err(CommandError::Disconnected);
+			reconnect(connection, args)?;
+
+			if let Some(command) = retry_command {
+				print_note("Retrying command.");
+				return handle_client_command(command, connection, args);
+			}
+
+			return Err(CommandError::CommandFailed(err.to_string()));
 		},
 
-		Err(err) => print_err(&err.to_string()),
+		Err(err) => return Err(report_error(&err.to_string(), args)),
 	}
 
 	Ok(())
 }
 
-fn handle_cli_command(command: CliCommand, parser: &mut CommandParser) -> Result<(), CommandError> {
+/// Prints the response to a command. Returns an error if the response could
+/// not be printed or the command failed for any of its keys, both of which
+/// are reported when it is printed.
+fn print_response(
+	response: &Response,
+	view: &View,
+	elapsed: Option<Duration>,
+	watch: bool,
+	args: &Args,
+) -> Result<(), CommandError> {
+	if let Err(err) = output::print_response(args.output, response, view, elapsed, watch) {
+		return Err(report_error(&err.to_string(), args));
+	}
+
+	match response.has_errors() {
+		true => Err(CommandError::CommandFailed("failed for some keys".to_owned())),
+		false => Ok(()),
+	}
+}
+
+/// Lets the user pick one of the configured policies and sets it.
+fn handle_policy_pick(
+	status: &Status,
+	connection: &mut Connection,
+	args: &Args,
+) -> Result<(), CommandError> {
+	let policies = status.policies();
+
+	let names = policies
+		.iter()
+		.map(ToString::to_string)
+		.collect::<Vec<_>>();
+
+	let current = policies
+		.iter()
+		.position(|policy| policy == status.policy())
+		.unwrap_or(0);
+
+	let title = "Select a policy (arrows to move, enter to select, esc to cancel):";
+
+	let Ok(Some(index)) = picker::pick(title, &names, current) else {
+		print_note("No changes made.");
+		return Ok(());
+	};
+
+	let policy = policies[index];
+
+	// an auto policy is changed even if it is currently using the same one
+	if policy == *status.policy() && !status.is_auto_policy() {
+		print_note("No changes made.");
+		return Ok(());
+	}
+
+	handle_client_command(ClientCommand::Policy(Some(policy)), connection, args)
+}
+
+/// Opens the fetched value in the user's editor and sets the edited value
+/// back to the cache with the supplied TTL.
+fn handle_edit(
+	key: String,
+	buf: &[u8],
+	ttl: Option<u32>,
+	connection: &mut Connection,
+	args: &Args,
+) -> Result<(), CommandError> {
+	match editor::edit(buf) {
+		Ok(Some(value)) => {
+			handle_client_command(ClientCommand::Set(key, value, ttl), connection, args)
+		},
+
+		Ok(None) => {
+			print_note("No changes made.");
+			Ok(())
+		},
+
+		Err(err) => Err(report_error(&err.to_string(), args)),
+	}
+}
+
+/// Sends each part of a multi-key command to the connection which owns its
+/// keys and prints the combined results in the order the keys were given.
+fn handle_split_command(
+	command: &ClientCommand,
+	parts: Vec<(String, ClientCommand)>,
+	connections: &mut Connections,
+	args: &Args,
+) -> Result<(), CommandError> {
+	if matches!(command, ClientCommand::Diff(..)) {
+		return match send_split_diff(parts, connections) {
+			Ok(response) => print_response(&response, &command.view(), None, false, args),
+
+			Err(err) => Err(report_error(&err.to_string(), args)),
+		};
+	}
+
+	let action = command.action().ok_or(CommandError::CrossShardKeys)?;
+
+	let mut results = KeyResults::new(action);
+
+	for (name, part) in parts {
+		let keys = part
+			.keys()
+			.into_iter()
+			.map(ToOwned::to_owned)
+			.collect::<Vec<_>>();
+
+		let result = connections
+			.get(&name)
+			.and_then(|connection| connection.send(part));
+
+		match result {
+			Ok(Response::Keys(part_results)) => results.extend(part_results),
+
+			Ok(_) => {},
+
+			Err(err) => {
+				// the error applies to every key in the part
+				for key in keys {
+					let err = CommandError::ShardFailed(name.clone(), err.to_string());
+					results.push(KeyResult::new(key, Err(err)));
+				}
+			},
+		}
+	}
+
+	results.sort(&command.keys());
+
+	print_response(&Response::Keys(results), &command.view(), None, false, args)
+}
+
+/// Peeks each key of a diff on the connection which owns it and returns the
+/// values to be compared.
+fn send_split_diff(
+	parts: Vec<(String, ClientCommand)>,
+	connections: &mut Connections,
+) -> Result<Response, CommandError> {
+	let mut values = Vec::new();
+
+	for (name, part) in parts {
+		let ClientCommand::Peek(key, _) = &part else {
+			continue;
+		};
+
+		let key = key.clone();
+
+		let response = connections
+			.get(&name)
+			.and_then(|connection| connection.send(part))
+			.map_err(|err| CommandError::ShardFailed(name, err.to_string()))?;
+
+		if let Response::Bytes(value) = response {
+			values.push((key, value));
+		}
+	}
+
+	<[_; 2]>::try_from(values)
+		.map(Response::Diff)
+		.map_err(|_| CommandError::CrossShardKeys)
+}
+
+fn handle_fan_out_command(
+	command: ClientCommand,
+	connections: &mut Connections,
+	args: &Args,
+) -> Result<(), CommandError> {
+	let is_status_watch = matches!(command, ClientCommand::Status(true));
+	let view = command.view();
+
+	if view.out().is_some() {
+		return Err(CommandError::GroupOutputFile);
+	}
+
+	if matches!(command, ClientCommand::Edit(..)) {
+		return Err(CommandError::GroupEdit);
+	}
+
+	if !confirm_fan_out_command(&command, connections, args) {
+		print_note("Cancelled.");
+		return Err(CommandError::CommandFailed("cancelled".to_owned()));
+	}
+
+	let results = fanout::send(&command, connections);
+
+	if is_status_watch && args.output.is_text() {
+		CliCommand::Clear.run()?;
+	}
+
+	if let Err(err) = output::print_host_results(args.output, &results, &view, is_status_watch) {
+		return Err(report_error(&err.to_string(), args));
+	}
+
+	if is_status_watch {
+		if args.output.is_text() {
+			print_note("Watching cache status... Ctrl-C to exit.");
+		}
+
+		thread::sleep(Duration::from_secs(1));
+		return handle_fan_out_command(ClientCommand::Status(true), connections, args);
+	}
+
+	// the command failed if it failed on any host, which is reported along
+	// with the other results
+	let is_failed = results
+		.iter()
+		.any(|result| result.result().map_or(true, Response::has_errors));
+
+	match is_failed {
+		true => Err(CommandError::CommandFailed("failed on some hosts".to_owned())),
+		false => Ok(()),
+	}
+}
+
+/// Summarizes the change a destructive command makes to the connection's
+/// cache and the objects in it, or returns `None` if the command does not
+/// need to be confirmed. A resize which evicts objects is warned about even
+/// if it is not confirmed.
+fn change_summary(
+	command: &ClientCommand,
+	host: &str,
+	client: &mut PaperClient,
+	format: Format,
+) -> Result<Option<String>, CommandError> {
+	if !command.is_destructive() {
+		return Ok(None);
+	}
+
+	let status = client.status()?;
+
+	if let ClientCommand::Resize(size) = command {
+		let resize = Resize::new(&status, *size)?;
+
+		if resize.is_evicting() {
+			output::print_eviction_warning(format, host, &resize);
+		}
+	}
+
+	let summary = command.change_description(&status)?.map(|description| {
+		format!(
+			"{description} on {host} ({} objects, {} used)",
+			fmt::number(status.num_objects()),
+			fmt::memory(status.used_size(), Some(2)),
+		)
+	});
+
+	Ok(summary)
+}
+
+/// Asks the user to confirm a destructive command once for every host it
+/// changes. Returns `false` if the command should not be sent.
+fn confirm_fan_out_command(
+	command: &ClientCommand,
+	connections: &mut Connections,
+	args: &Args,
+) -> bool {
+	if !command.is_destructive() {
+		return true;
+	}
+
+	let mut num_hosts = 0;
+
+	for (name, connection) in connections.iter_mut() {
+		let is_confirmed = args.yes && !connection.always_confirm();
+
+		let summary = connection
+			.client()
+			.and_then(|client| change_summary(command, name, client, args.output));
+
+		match summary {
+			Ok(Some(summary)) if !is_confirmed => print_note(&summary),
+			Ok(_) => continue,
+
+			// the command fails on this host as well, which is reported
+			// with the other results
+			Err(_) if is_confirmed => continue,
+			Err(err) => print_note(&format!("{name}: {err}")),
+		}
+
+		num_hosts += 1;
+	}
+
+	num_hosts == 0 || line_reader::confirm(&format!("Continue on {num_hosts} hosts?")).unwrap_or(false)
+}
+
+fn reconnect(connection: &mut Connection, args: &Args) -> Result<(), CommandError> {
+	for attempt in 0..args.reconnect_attempts {
+		print_countdown(
+			connection::backoff(attempt),
+			attempt + 1,
+			args.reconnect_attempts,
+		);
+
+		match connection.reconnect() {
+			Ok(_) => {
+				print_note("Reconnected.");
+				return Ok(());
+			},
+
+			Err(err) => print_err(&err.to_string()),
+		}
+	}
+
+	// the session continues, and the connection can be reconnected later
+	connection.disconnect();
+	Err(CommandError::Disconnected)
+}
+
+fn handle_cli_command(
+	command: CliCommand,
+	connections: &mut Connections,
+	parser: &mut CommandParser,
+	recorder: &mut Option<Recorder>,
+) -> Result<(), CommandError> {
 	if command.is_quit() {
 		parser.close();
 		return Err(CommandError::Interrupted);
 	}
 
+	// the hosts of a fan-out or sharded session are fixed, since the keys
+	// owned by each host would change with them
+	if command.is_host_change() && !matches!(connections.mode(), Mode::Single) {
+		return Err(CommandError::FixedHosts);
+	}
+
 	if command.is_help() {
 		print_ok("Supported commands:");
 		parser.print_hints(Some("  "));
 	}
 
-	if let Err(err) = command.run() {
-		print_err(&err.to_string());
+	let result = match &command {
+		CliCommand::Connect(target) => Connection::open(target).map(|connection| {
+			let name = connections.active_name().to_owned();
+			connections.open(&name, connection);
+		}),
+
+		CliCommand::Disconnect => {
+			connections.active().disconnect();
+			Ok(())
+		},
+
+		CliCommand::Reconnect => connections
+			.active()
+			.reconnect()
+			.map_err(CommandError::from),
+
+		CliCommand::Open(name, target) => Connection::open(target).map(|connection| {
+			connections.open(name, connection);
+		}),
+
+		CliCommand::Use(name) => connections.select(name),
+		CliCommand::Close(name) => connections.close(name),
+
+		CliCommand::Connections => {
+			print_connections(connections);
+			Ok(())
+		},
+
+		CliCommand::Shard(key) => connections.shard(key).map(|name| {
+			print_ok(&format!("{key} -> {name}"));
+		}),
+
+		CliCommand::Record(Some(path)) => match recorder {
+			Some(_) => Err(CommandError::AlreadyRecording),
+
+			None => Recorder::open(Path::new(path)).map(|new_recorder| {
+				*recorder = Some(new_recorder);
+				print_ok(&format!("recording session to {path}"));
+			}),
+		},
+
+		CliCommand::Record(None) => match recorder.take() {
+			Some(_) => {
+				print_ok("stopped recording session");
+				Ok(())
+			},
+
+			None => Err(CommandError::NotRecording),
+		},
+
+		_ => command.run(),
+	};
+
+	result?;
+
+	if command.is_connection() {
+		update_read_only(connections, parser);
+		update_prompt(connections, parser);
+		print_active_connection(connections);
 	}
 
 	Ok(())
 }
 
-fn print_ok(message: &str) {
-	println!("\x1B[33mOk\x1B[0m: {message}");
+/// Rejects commands which change the cache for the rest of the session once
+/// it is read-only, or a connection is opened with a read-only profile.
+fn update_read_only(connections: &mut Connections, parser: &mut CommandParser) {
+	let is_read_only = parser.is_read_only()
+		|| connections
+			.iter()
+			.any(|(_, connection)| connection.is_read_only());
+
+	if !is_read_only {
+		return;
+	}
+
+	parser.set_read_only();
+
+	for (_, connection) in connections.iter_mut() {
+		connection.set_read_only();
+	}
 }
 
-fn print_err(message: &str) {
-	println!("\x1B[31mErr\x1B[0m: {message}");
+fn update_prompt(connections: &mut Connections, parser: &mut CommandParser) {
+	match connections.mode() {
+		Mode::Single => {},
+
+		Mode::FanOut => {
+			parser.set_group("all", connections.len());
+			return;
+		},
+
+		Mode::Sharded(_) => {
+			parser.set_group("shards", connections.len());
+			return;
+		},
+	}
+
+	let name = match connections.len() {
+		1 => None,
+		_ => Some(connections.active_name().to_owned()),
+	};
+
+	let connection = connections.active();
+
+	let addr = match connection.is_connected() {
+		true => Some(connection.addr()),
+		false => None,
+	};
+
+	parser.set_target(name.as_deref(), addr);
+}
+
+fn print_active_connection(connections: &mut Connections) {
+	let name = connections.active_name().to_owned();
+	let connection = connections.active();
+
+	match connection.is_connected() {
+		true => print_ok(&format!("[{name}] connected to {}", connection.addr())),
+		false => print_ok(&format!("[{name}] disconnected from {}", connection.addr())),
+	}
 }
 
-fn print_note(message: &str) {
-	println!("\x1B[36mNote\x1B[0m: {message}");
+fn print_connections(connections: &Connections) {
+	print_ok("Open connections:");
+
+	for (name, connection) in connections.iter() {
+		let marker = match name == connections.active_name() {
+			true => "*",
+			false => " ",
+		};
+
+		let state = match connection.is_connected() {
+			true => "connected",
+			false => "disconnected",
+		};
+
+		println!("{marker} {name}\t{}\t{state}", connection.addr());
+	}
+}
+
+fn print_countdown(delay: Duration, attempt: u32, max_attempts: u32) {
+	let mut stdout = io::stdout();
+
+	for remaining in (1..=delay.as_secs()).rev() {
+		print!(
+			"\r\x1B[K\x1B[36mNote\x1B[0m: reconnecting in {remaining}s (attempt {attempt}/{max_attempts})... Ctrl-C to exit.",
+		);
+
+		let _ = stdout.flush();
+		thread::sleep(Duration::from_secs(1));
+	}
+
+	print!("\r\x1B[K");
+	let _ = stdout.flush();
 }
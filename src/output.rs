@@ -0,0 +1,1307 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	fs,
+	io::{self, Write},
+	path::Path,
+	time::Duration,
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use clap::ValueEnum;
+use kwik::{
+	fmt,
+	table::{Align, Row, Style, Table},
+};
+use paper_client::{PaperValue, Status};
+use serde_json::{Map, Value, json};
+
+use crate::{
+	command::{
+		client::Response,
+		codec::Number,
+		digest::Digest,
+		error::CommandError,
+		multi::{KeyResult, KeyResults, Outcome},
+		resize::Resize,
+		view::{Encoding, View},
+	},
+	fanout::{HostResult, StatusTotals},
+	output::{
+		diff::{TextDiff, first_difference},
+		hexdump::{hex, hexdump},
+	},
+	record::Entry,
+};
+
+mod diff;
+mod hexdump;
+mod json;
+mod pager;
+
+const SUCCESS_MESSAGE: &str = "done";
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+	/// Coloured, human-readable text
+	Text,
+
+	/// One JSON object per response
+	Json,
+
+	/// Only the response value, without any decoration
+	Raw,
+
+	/// Human-readable text with tabular status output
+	Table,
+}
+
+impl Format {
+	/// Returns `true` if the format is intended to be read by a person rather
+	/// than another program.
+	pub fn is_text(&self) -> bool {
+		matches!(self, Format::Text | Format::Table)
+	}
+}
+
+/// Prints the response to a client command, with values displayed according
+/// to the supplied view. The elapsed time is appended to text output if
+/// supplied.
+pub fn print_response(
+	format: Format,
+	response: &Response,
+	view: &View,
+	elapsed: Option<Duration>,
+	watch: bool,
+) -> Result<(), CommandError> {
+	if let (Some(path), Response::Bytes(value)) = (view.out(), response) {
+		return save_value(format, path, value.into());
+	}
+
+	match (format, response) {
+		(Format::Text, Response::Status(status)) => {
+			print_paged(&ok_message(&status_text(status, watch)), watch);
+		},
+
+		(Format::Table, Response::Status(status)) => {
+			let table = table_string(&status_table(status));
+			print_paged(&format!("{}\n{table}", ok_message(&status_title(watch))), watch);
+		},
+
+		(Format::Text | Format::Table, response) => {
+			let mut message = response_text(response, view)?;
+
+			if let Some(elapsed) = elapsed {
+				message += &format!(" ({elapsed:?})");
+			}
+
+			print_paged(&ok_message(&message), watch);
+		},
+
+		(Format::Json, response) => {
+			let mut value = json!({
+				"ok": true,
+			});
+
+			insert_response_json(&mut value, response, view)?;
+
+			println!("{value}");
+		},
+
+		(Format::Raw, Response::Status(status)) => {
+			print_raw(status_text(status, watch).as_bytes());
+		},
+
+		(Format::Raw, response) => print_raw_response(response, view)?,
+	}
+
+	Ok(())
+}
+
+/// Writes the raw bytes of the value to the file and reports how many bytes
+/// were written.
+fn save_value(format: Format, path: &Path, buf: &[u8]) -> Result<(), CommandError> {
+	fs::write(path, buf).map_err(|_| CommandError::InvalidOutputFile)?;
+
+	match format {
+		Format::Text | Format::Table => print_ok(&format!(
+			"wrote {} ({} B) to {}",
+			fmt::memory(buf.len(), Some(2)),
+			buf.len(),
+			path.display(),
+		)),
+
+		Format::Json => {
+			let value = json!({
+				"ok": true,
+				"path": path.display().to_string(),
+				"size": buf.len(),
+			});
+
+			println!("{value}");
+		},
+
+		Format::Raw => {},
+	}
+
+	Ok(())
+}
+
+/// Prints the error returned by a client command.
+pub fn print_error(format: Format, message: &str) {
+	match format {
+		Format::Text | Format::Table => print_err(message),
+
+		Format::Json => {
+			let value = json!({
+				"ok": false,
+				"error": message,
+			});
+
+			println!("{value}");
+		},
+
+		Format::Raw => eprintln!("{message}"),
+	}
+}
+
+/// Prints the results of a command which was sent to several hosts. If every
+/// host returned its status, the statuses are summarized in a single table
+/// followed by their totals.
+pub fn print_host_results(
+	format: Format,
+	results: &[HostResult],
+	view: &View,
+	watch: bool,
+) -> Result<(), CommandError> {
+	let statuses = results
+		.iter()
+		.filter_map(|result| match result.result() {
+			Ok(Response::Status(status)) => Some(status),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+
+	let is_status = !statuses.is_empty()
+		&& results
+			.iter()
+			.all(|result| matches!(result.result(), Ok(Response::Status(_)) | Err(_)));
+
+	match format {
+		Format::Text | Format::Table => {
+			let table = match is_status {
+				true => host_status_table(results, &StatusTotals::new(&statuses)),
+				false => host_value_table(results, view)?,
+			};
+
+			let title = ok_message(&format!("{} hosts", results.len()));
+			print_paged(&format!("{title}\n{}", table_string(&table)), watch);
+		},
+
+		Format::Json => {
+			let hosts = results
+				.iter()
+				.map(|result| match result.result() {
+					Ok(response) => {
+						let mut value = json!({
+							"host": result.host(),
+							"ok": true,
+						});
+
+						insert_response_json(&mut value, response, view)?;
+
+						Ok(value)
+					},
+
+					Err(err) => Ok(json!({
+						"host": result.host(),
+						"ok": false,
+						"error": err.to_string(),
+					})),
+				})
+				.collect::<Result<Vec<_>, CommandError>>()?;
+
+			let mut value = json!({
+				"hosts": hosts,
+			});
+
+			if is_status {
+				value["total"] = totals_json(&StatusTotals::new(&statuses));
+			}
+
+			println!("{value}");
+		},
+
+		Format::Raw => {
+			for result in results {
+				match result.result() {
+					Ok(response) => print_raw_response(response, view)?,
+					Err(err) => eprintln!("{}: {err}", result.host()),
+				}
+			}
+		},
+	}
+
+	Ok(())
+}
+
+/// Warns that resizing the host's cache will evict objects, before it is
+/// resized.
+pub fn print_eviction_warning(format: Format, host: &str, resize: &Resize) {
+	let message = format!(
+		"resizing {host} from {} to {}: {}",
+		fmt::memory(resize.old_size(), Some(2)),
+		fmt::memory(resize.new_size(), Some(2)),
+		eviction_text(resize),
+	);
+
+	match format {
+		Format::Text | Format::Table => print_warning(&message),
+
+		// the warning is kept out of the output, which may be parsed
+		Format::Json | Format::Raw => eprintln!("warning: {message}"),
+	}
+}
+
+/// Prints the requests a dry run would have sent, each with the host it
+/// would have been sent to.
+pub fn print_dry_run(format: Format, requests: &[(String, String)]) {
+	match format {
+		Format::Text | Format::Table => {
+			let noun = match requests.len() {
+				1 => "request",
+				_ => "requests",
+			};
+
+			let mut text = ok_message(&format!(
+				"dry run, {} {noun} would be sent",
+				fmt::number(requests.len()),
+			));
+
+			for (host, request) in requests {
+				text += &format!("\n  {host}  {request}");
+			}
+
+			print_paged(&text, false);
+		},
+
+		Format::Json => {
+			let requests = requests
+				.iter()
+				.map(|(host, request)| {
+					json!({
+						"host": host,
+						"request": request,
+					})
+				})
+				.collect::<Vec<_>>();
+
+			let value = json!({
+				"ok": true,
+				"dry_run": true,
+				"requests": requests,
+			});
+
+			println!("{value}");
+		},
+
+		Format::Raw => {
+			for (host, request) in requests {
+				println!("{host}\t{request}");
+			}
+		},
+	}
+}
+
+/// Returns the result of a command sent to the host as it is printed with
+/// the JSON format.
+pub fn result_json(host: &str, result: &Result<Response, CommandError>, view: &View) -> Value {
+	let mut value = json!({
+		"host": host,
+		"ok": true,
+	});
+
+	let result = result
+		.as_ref()
+		.map_err(ToString::to_string)
+		.and_then(|response| {
+			insert_response_json(&mut value, response, view).map_err(|err| err.to_string())
+		});
+
+	match result {
+		Ok(_) => value,
+
+		Err(err) => json!({
+			"host": host,
+			"ok": false,
+			"error": err,
+		}),
+	}
+}
+
+/// Prints the responses and error a replayed input was expected to receive
+/// (i.e., those in the recording) and those it actually received.
+pub fn print_replay_difference(format: Format, expected: &Entry, actual: &Entry) {
+	match format {
+		Format::Text | Format::Table => {
+			let mut text = warning_message(&format!(
+				"response to \"{}\" differs from the recording",
+				expected.input(),
+			));
+
+			for (sign, colour, entry) in [("-", 31, expected), ("+", 32, actual)] {
+				for response in entry.responses() {
+					text += &format!("\n\x1B[{colour}m{sign} {response}\x1B[0m");
+				}
+
+				if let Some(err) = entry.error() {
+					text += &format!("\n\x1B[{colour}m{sign} error: {err}\x1B[0m");
+				}
+			}
+
+			println!("{text}");
+		},
+
+		Format::Json => {
+			let value = json!({
+				"ok": false,
+				"input": expected.input(),
+				"expected": replay_json(expected),
+				"actual": replay_json(actual),
+			});
+
+			println!("{value}");
+		},
+
+		Format::Raw => eprintln!("response to \"{}\" differs from the recording", expected.input()),
+	}
+}
+
+pub fn print_ok(message: &str) {
+	println!("{}", ok_message(message));
+}
+
+pub fn print_err(message: &str) {
+	println!("\x1B[31mErr\x1B[0m: {message}");
+}
+
+pub fn print_note(message: &str) {
+	println!("\x1B[36mNote\x1B[0m: {message}");
+}
+
+pub fn print_warning(message: &str) {
+	println!("{}", warning_message(message));
+}
+
+fn ok_message(message: &str) -> String {
+	format!("\x1B[33mOk\x1B[0m: {message}")
+}
+
+fn warning_message(message: &str) -> String {
+	format!("\x1B[35mWarning\x1B[0m: {message}")
+}
+
+/// Prints the text, through the pager if it does not fit in the terminal.
+/// Output which is refreshed while watching is never paged.
+fn print_paged(text: &str, watch: bool) {
+	if !watch && pager::is_needed(text) && pager::page(text).is_ok() {
+		return;
+	}
+
+	println!("{text}");
+}
+
+fn table_string(table: &Table) -> String {
+	let mut buf = Vec::new();
+	table.print(&mut buf);
+
+	String::from_utf8_lossy(&buf)
+		.trim_end()
+		.to_owned()
+}
+
+fn replay_json(entry: &Entry) -> Value {
+	json!({
+		"responses": entry.responses(),
+		"error": entry.error(),
+	})
+}
+
+/// Writes the bytes to stdout as they are, followed by a new line so that
+/// the values of several commands are separated when they are piped. A value
+/// is written byte-for-byte with `--out`.
+fn print_raw(buf: &[u8]) {
+	let _ = write_raw(&mut io::stdout(), buf);
+}
+
+fn write_raw(out: &mut impl Write, buf: &[u8]) -> io::Result<()> {
+	out.write_all(buf)?;
+	out.write_all(b"\n")?;
+	out.flush()
+}
+
+/// Prints the response's value in raw form. Commands which do not return a
+/// value print nothing. Values are written as they are unless an encoding
+/// was explicitly requested.
+fn print_raw_response(response: &Response, view: &View) -> Result<(), CommandError> {
+	match response {
+		Response::Done => {},
+		Response::Bool(value) => print_raw(value.to_string().as_bytes()),
+
+		Response::Bytes(value) => {
+			let buf: &[u8] = value.into();
+
+			if let Some(number) = value_number(value, view)? {
+				print_raw(number.to_string().as_bytes());
+				return Ok(());
+			}
+
+			if view.is_json()
+				&& let Some(json) = value_json(value, view)?
+			{
+				print_raw(json::format(&json, view.is_compact(), false).as_bytes());
+				return Ok(());
+			}
+
+			match view.encoding() {
+				Encoding::Auto => print_raw(buf),
+				Encoding::Hex => print_raw(hex(buf).as_bytes()),
+				Encoding::Base64 => print_raw(STANDARD.encode(buf).as_bytes()),
+			}
+		},
+
+		Response::Size(size) => print_raw(size.to_string().as_bytes()),
+		Response::Status(status) => print_raw(status_text(status, false).as_bytes()),
+
+		Response::Digest(digest) => print_raw(hex(digest.hash()).as_bytes()),
+		Response::Diff(values) => print_raw(diff_text(values, false).as_bytes()),
+
+		Response::Keys(results) => {
+			let lines = results
+				.results()
+				.iter()
+				.map(|result| format!("{}\t{}", result.key(), key_cell(result, results.action(), view)))
+				.collect::<Vec<_>>();
+
+			print_raw(lines.join("\n").as_bytes());
+		},
+
+		Response::Resize(resize) => {
+			print_raw(format!("{}\t{}", resize.old_size(), resize.new_size()).as_bytes());
+		},
+
+		Response::Policies(status) => print_raw(policy_str(status).as_bytes()),
+	}
+
+	Ok(())
+}
+
+/// Returns the value as a string if it is valid UTF-8.
+fn value_str(value: &PaperValue) -> Option<&str> {
+	value.try_into().ok()
+}
+
+/// Returns the value as a string if it is valid UTF-8 and can be printed
+/// to a terminal without any of its characters being lost.
+fn value_printable(value: &PaperValue) -> Option<&str> {
+	value_str(value).filter(|value| {
+		value
+			.chars()
+			.all(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+	})
+}
+
+/// Decodes the value if the view specifies a type.
+fn value_number(value: &PaperValue, view: &View) -> Result<Option<Number>, CommandError> {
+	view.codec()
+		.map(|codec| codec.decode(value.into()))
+		.transpose()
+}
+
+/// Parses the value as JSON if the view requires it or if the value looks
+/// like a JSON object or array, and selects the field at the view's path.
+/// Values which cannot be parsed are displayed as they are unless a path
+/// was supplied.
+fn value_json(value: &PaperValue, view: &View) -> Result<Option<Value>, CommandError> {
+	if view.encoding() != Encoding::Auto || view.codec().is_some() {
+		return Ok(None);
+	}
+
+	let text = value_str(value);
+
+	let is_json_like = text
+		.map(str::trim_start)
+		.is_some_and(|text| text.starts_with('{') || text.starts_with('['));
+
+	if !view.is_json() && !is_json_like {
+		return Ok(None);
+	}
+
+	let json = text.and_then(|text| serde_json::from_str::<Value>(text).ok());
+
+	match (json, view.path()) {
+		(Some(json), Some(path)) => match path.select(&json) {
+			Some(json) => Ok(Some(json.clone())),
+			None => Err(CommandError::UnknownJsonPath),
+		},
+
+		(Some(json), None) => Ok(Some(json)),
+		(None, Some(_)) => Err(CommandError::InvalidJson),
+		(None, None) => Ok(None),
+	}
+}
+
+/// Formats the value as text, falling back to a hex dump if the value is
+/// not printable.
+fn value_text(value: &PaperValue, view: &View) -> Result<String, CommandError> {
+	let buf: &[u8] = value.into();
+
+	if let Some(number) = value_number(value, view)? {
+		return Ok(number.to_string());
+	}
+
+	if let Some(json) = value_json(value, view)? {
+		return Ok(json::format(&json, view.is_compact(), true));
+	}
+
+	let text = match (view.encoding(), value_printable(value)) {
+		(Encoding::Auto, Some(value)) => value.to_owned(),
+		(Encoding::Auto | Encoding::Hex, _) => hexdump(buf),
+		(Encoding::Base64, _) => STANDARD.encode(buf),
+	};
+
+	Ok(text)
+}
+
+/// Formats the value for a single table cell, in which a hex dump would not
+/// fit.
+fn value_cell(value: &PaperValue, view: &View) -> String {
+	let buf: &[u8] = value.into();
+
+	match value_number(value, view) {
+		Ok(Some(number)) => return number.to_string(),
+		Err(err) => return format!("Err: {err}"),
+		Ok(None) => {},
+	}
+
+	match value_json(value, view) {
+		Ok(Some(json)) => return json::format(&json, true, false),
+		Err(err) => return format!("Err: {err}"),
+		Ok(None) => {},
+	}
+
+	match (view.encoding(), value_printable(value)) {
+		(Encoding::Auto, Some(value)) => value.to_owned(),
+		(Encoding::Auto, None) => format!("<{} B binary>", buf.len()),
+		(Encoding::Hex, _) => hex(buf),
+		(Encoding::Base64, _) => STANDARD.encode(buf),
+	}
+}
+
+fn response_text(response: &Response, view: &View) -> Result<String, CommandError> {
+	let text = match response {
+		Response::Done => SUCCESS_MESSAGE.into(),
+		Response::Bool(value) => value.to_string(),
+		Response::Bytes(value) => value_text(value, view)?,
+		Response::Size(size) => format!("{} ({size} B)", fmt::memory(*size, Some(2))),
+		Response::Status(status) => status_text(status, false),
+
+		Response::Digest(digest) => digest_text(digest),
+		Response::Diff(values) => diff_text(values, true),
+
+		Response::Keys(results) => {
+			format!("{}\n{}", keys_summary(results), table_string(&keys_table(results, view)))
+		},
+
+		// any eviction is warned about before the cache is resized
+		Response::Resize(resize) => resize_text(resize),
+
+		Response::Policies(status) => policies_text(status),
+	};
+
+	Ok(text)
+}
+
+/// Inserts the response's value into the JSON object. Values which are not
+/// valid UTF-8, or for which an encoding was requested, are encoded and
+/// accompanied by an `encoding` field.
+fn insert_response_json(
+	object: &mut Value,
+	response: &Response,
+	view: &View,
+) -> Result<(), CommandError> {
+	let (value, encoding) = match response {
+		Response::Done => (Value::Null, None),
+		Response::Bool(value) => (Value::from(*value), None),
+
+		Response::Bytes(value) => {
+			let buf: &[u8] = value.into();
+
+			if let Some(number) = value_number(value, view)? {
+				object["value"] = number_json(number);
+				return Ok(());
+			}
+
+			if view.is_json()
+				&& let Some(json) = value_json(value, view)?
+			{
+				object["value"] = json;
+				return Ok(());
+			}
+
+			match (view.encoding(), value_str(value)) {
+				(Encoding::Auto, Some(value)) => (Value::from(value), None),
+				(Encoding::Hex, _) => (Value::from(hex(buf)), Some("hex")),
+				(Encoding::Auto | Encoding::Base64, _) => {
+					(Value::from(STANDARD.encode(buf)), Some("base64"))
+				},
+			}
+		},
+
+		Response::Size(size) => (Value::from(*size), None),
+		Response::Status(status) => (status_json(status), None),
+
+		Response::Digest(digest) => (digest_json(digest), None),
+		Response::Diff(values) => (diff_json(values), None),
+		Response::Keys(results) => (keys_json(results, view)?, None),
+		Response::Resize(resize) => (resize_json(resize), None),
+		Response::Policies(status) => (policies_json(status), None),
+	};
+
+	object["value"] = value;
+
+	if let Some(encoding) = encoding {
+		object["encoding"] = Value::from(encoding);
+	}
+
+	Ok(())
+}
+
+fn digest_text(digest: &Digest) -> String {
+	format!(
+		"{} ({} B) {}:{}",
+		fmt::memory(digest.size(), Some(2)),
+		digest.size(),
+		digest.algorithm(),
+		hex(digest.hash()),
+	)
+}
+
+fn digest_json(digest: &Digest) -> Value {
+	json!({
+		"size": digest.size(),
+		"algorithm": digest.algorithm().to_string(),
+		"hash": hex(digest.hash()),
+	})
+}
+
+/// Describes how the two values differ, as a line diff if both values are
+/// text or the offset of the first differing byte otherwise.
+fn diff_text([(a_key, a), (b_key, b)]: &[(String, PaperValue); 2], colour: bool) -> String {
+	let a_buf: &[u8] = a.into();
+	let b_buf: &[u8] = b.into();
+
+	let sizes = format!(
+		"{} vs {}",
+		fmt::memory(a_buf.len(), Some(2)),
+		fmt::memory(b_buf.len(), Some(2)),
+	);
+
+	if let (Some(a_text), Some(b_text)) = (value_printable(a), value_printable(b)) {
+		return match TextDiff::new(a_text, b_text, (a_key, b_key), colour) {
+			TextDiff::Identical => format!("values are identical ({} B)", a_buf.len()),
+			TextDiff::LineEndings => format!("values differ only in line endings ({sizes})"),
+
+			TextDiff::TooLarge(line) => {
+				format!("values differ from line {line}, too many changes to show ({sizes})")
+			},
+
+			TextDiff::Lines(lines) => format!("values differ ({sizes})\n{lines}"),
+		};
+	}
+
+	match first_difference(a_buf, b_buf) {
+		None => format!("values are identical ({} B)", a_buf.len()),
+
+		Some(offset) => {
+			let byte = |buf: &[u8]| match buf.get(offset) {
+				Some(byte) => format!("{byte:02x}"),
+				None => "end".into(),
+			};
+
+			format!(
+				"values differ at offset {offset} (0x{offset:x}): {} vs {} ({sizes})",
+				byte(a_buf),
+				byte(b_buf),
+			)
+		},
+	}
+}
+
+fn diff_json(values: &[(String, PaperValue); 2]) -> Value {
+	let [(a_key, a), (b_key, b)] = values;
+
+	let a_buf: &[u8] = a.into();
+	let b_buf: &[u8] = b.into();
+
+	let mut value = json!({
+		"identical": a_buf == b_buf,
+		"sizes": [a_buf.len(), b_buf.len()],
+	});
+
+	match (value_printable(a), value_printable(b)) {
+		(Some(a_text), Some(b_text)) => {
+			value["diff"] = match TextDiff::new(a_text, b_text, (a_key, b_key), false) {
+				TextDiff::Lines(lines) => Value::from(lines),
+				_ => Value::Null,
+			};
+		},
+
+		_ => value["offset"] = Value::from(first_difference(a_buf, b_buf)),
+	}
+
+	value
+}
+
+/// Formats the result for one key of a multi-key command for a single
+/// table cell.
+fn key_cell(result: &KeyResult, action: &str, view: &View) -> String {
+	match (result.outcome(), result.result()) {
+		(Outcome::Missing, _) => "(missing)".into(),
+		(_, Err(err)) => format!("Err: {err}"),
+
+		(_, Ok(Response::Done | Response::Bool(_))) => action.into(),
+		(_, Ok(Response::Bytes(value))) => value_cell(value, view),
+
+		// only the first line of a longer response fits in a cell
+		(_, Ok(response)) => match response_text(response, view) {
+			Ok(text) => text.lines().next().unwrap_or_default().to_owned(),
+			Err(err) => format!("Err: {err}"),
+		},
+	}
+}
+
+fn keys_summary(results: &KeyResults) -> String {
+	format!(
+		"{} {}, {} missing, {} errors",
+		results.count(Outcome::Ok),
+		results.action(),
+		results.count(Outcome::Missing),
+		results.count(Outcome::Error),
+	)
+}
+
+fn keys_table(results: &KeyResults, view: &View) -> Table {
+	let mut table = Table::default();
+
+	table.set_header(
+		Row::default()
+			.push("key", Align::Center, Style::Bold)
+			.push("result", Align::Center, Style::Bold),
+	);
+
+	for result in results.results() {
+		let row = Row::default()
+			.push(result.key(), Align::Left, Style::Normal)
+			.push(key_cell(result, results.action(), view), Align::Left, Style::Normal);
+
+		table.add_row(row);
+	}
+
+	table
+}
+
+fn keys_json(results: &KeyResults, view: &View) -> Result<Value, CommandError> {
+	let mut keys = Vec::with_capacity(results.results().len());
+
+	for result in results.results() {
+		let mut value = json!({
+			"key": result.key(),
+			"ok": result.outcome() == Outcome::Ok,
+		});
+
+		match (result.outcome(), result.result()) {
+			(Outcome::Missing, _) => value["missing"] = Value::Bool(true),
+			(_, Err(err)) => value["error"] = Value::from(err.to_string()),
+			(_, Ok(response)) => insert_response_json(&mut value, response, view)?,
+		}
+
+		keys.push(value);
+	}
+
+	let mut summary = Map::new();
+
+	summary.insert(results.action().into(), results.count(Outcome::Ok).into());
+	summary.insert("missing".into(), results.count(Outcome::Missing).into());
+	summary.insert("errors".into(), results.count(Outcome::Error).into());
+
+	Ok(json!({
+		"keys": keys,
+		"summary": summary,
+	}))
+}
+
+fn resize_text(resize: &Resize) -> String {
+	format!(
+		"resized from {} to {}",
+		fmt::memory(resize.old_size(), Some(2)),
+		fmt::memory(resize.new_size(), Some(2)),
+	)
+}
+
+fn eviction_text(resize: &Resize) -> String {
+	format!(
+		"new size is below the used size of {}, so objects will be evicted",
+		fmt::memory(resize.used_size(), Some(2)),
+	)
+}
+
+fn resize_json(resize: &Resize) -> Value {
+	json!({
+		"old_size": resize.old_size(),
+		"new_size": resize.new_size(),
+		"used_size": resize.used_size(),
+		"evicting": resize.is_evicting(),
+	})
+}
+
+fn number_json(number: Number) -> Value {
+	match number {
+		Number::Unsigned(value) => Value::from(value),
+		Number::Signed(value) => Value::from(value),
+		Number::Float(value) => Value::from(value),
+	}
+}
+
+fn status_title(watch: bool) -> String {
+	match watch {
+		true => "PaperCache status (watching)".into(),
+		false => "PaperCache status".into(),
+	}
+}
+
+fn policy_str(status: &Status) -> String {
+	match status.is_auto_policy() {
+		true => format!("auto({})", status.policy()),
+		false => status.policy().to_string(),
+	}
+}
+
+/// Lists the configured policies, marking the one which is in use.
+fn policies_text(status: &Status) -> String {
+	let policies_str = status
+		.policies()
+		.iter()
+		.map(|policy| match policy == status.policy() {
+			true => format!("* {policy} (current)"),
+			false => format!("* {policy}"),
+		})
+		.collect::<Vec<_>>()
+		.join("\n");
+
+	format!("policy: {}\npolicies:\n{policies_str}", policy_str(status))
+}
+
+fn policies_json(status: &Status) -> Value {
+	let policies = status
+		.policies()
+		.iter()
+		.map(ToString::to_string)
+		.collect::<Vec<_>>();
+
+	json!({
+		"policy": status.policy().to_string(),
+		"is_auto_policy": status.is_auto_policy(),
+		"policies": policies,
+	})
+}
+
+fn status_text(status: &Status, watch: bool) -> String {
+	let title_output = status_title(watch);
+
+	let pid_output = format!("pid:\t\t{}", status.pid());
+
+	let max_size_output = format!(
+		"max_size:\t{} ({} B)",
+		fmt::memory(status.max_size(), Some(2)),
+		status.max_size(),
+	);
+
+	let used_size_output = format!(
+		"used_size:\t{} ({} B)",
+		fmt::memory(status.used_size(), Some(2)),
+		status.used_size(),
+	);
+
+	let num_objects_output = format!("num_objects:\t{}", fmt::number(status.num_objects()),);
+
+	let rss_output = format!(
+		"rss:\t\t{} ({} B)",
+		fmt::memory(status.rss(), Some(2)),
+		status.rss(),
+	);
+
+	let hwm_output = format!(
+		"hwm:\t\t{} ({} B)",
+		fmt::memory(status.hwm(), Some(2)),
+		status.hwm(),
+	);
+
+	let total_gets_output = format!("total_gets:\t{}", fmt::number(status.total_gets()),);
+	let total_sets_output = format!("total_sets:\t{}", fmt::number(status.total_sets()),);
+	let total_dels_output = format!("total_dels:\t{}", fmt::number(status.total_dels()),);
+
+	let miss_ratio_output = format!("miss_ratio:\t{:.3}", status.miss_ratio(),);
+
+	let policies_str = status
+		.policies()
+		.iter()
+		.map(|policy| format!("* {policy}"))
+		.collect::<Vec<_>>()
+		.join("\n");
+
+	let policies_output = format!("policies:\n{policies_str}");
+	let policy_output = format!("policy:\t\t{}", policy_str(status));
+
+	let uptime = format!("uptime:\t\t{}", fmt::timespan(status.uptime()),);
+
+	format!(
+		"{title_output}\n{pid_output}\n{max_size_output}\n{used_size_output}\n{num_objects_output}\n{rss_output}\n{hwm_output}\n{total_gets_output}\n{total_sets_output}\n{total_dels_output}\n{miss_ratio_output}\n{policies_output}\n{policy_output}\n{uptime}",
+	)
+}
+
+fn status_table(status: &Status) -> Table {
+	let policies = status
+		.policies()
+		.iter()
+		.map(ToString::to_string)
+		.collect::<Vec<_>>()
+		.join(", ");
+
+	let fields = [
+		("pid", status.pid().to_string()),
+		("max_size", fmt::memory(status.max_size(), Some(2))),
+		("used_size", fmt::memory(status.used_size(), Some(2))),
+		("num_objects", fmt::number(status.num_objects())),
+		("rss", fmt::memory(status.rss(), Some(2))),
+		("hwm", fmt::memory(status.hwm(), Some(2))),
+		("total_gets", fmt::number(status.total_gets())),
+		("total_sets", fmt::number(status.total_sets())),
+		("total_dels", fmt::number(status.total_dels())),
+		("miss_ratio", format!("{:.3}", status.miss_ratio())),
+		("policies", policies),
+		("policy", policy_str(status)),
+		("uptime", fmt::timespan(status.uptime())),
+	];
+
+	let mut table = Table::default();
+
+	table.set_header(
+		Row::default()
+			.push("field", Align::Center, Style::Bold)
+			.push("value", Align::Center, Style::Bold),
+	);
+
+	for (field, value) in fields {
+		let row = Row::default()
+			.push(field, Align::Left, Style::Normal)
+			.push(value, Align::Right, Style::Normal);
+
+		table.add_row(row);
+	}
+
+	table
+}
+
+fn status_json(status: &Status) -> Value {
+	let policies = status
+		.policies()
+		.iter()
+		.map(ToString::to_string)
+		.collect::<Vec<_>>();
+
+	json!({
+		"pid": status.pid(),
+		"max_size": status.max_size(),
+		"used_size": status.used_size(),
+		"num_objects": status.num_objects(),
+		"rss": status.rss(),
+		"hwm": status.hwm(),
+		"total_gets": status.total_gets(),
+		"total_sets": status.total_sets(),
+		"total_dels": status.total_dels(),
+		"miss_ratio": status.miss_ratio(),
+		"policies": policies,
+		"policy": status.policy().to_string(),
+		"is_auto_policy": status.is_auto_policy(),
+		"uptime": status.uptime(),
+	})
+}
+
+fn totals_json(totals: &StatusTotals) -> Value {
+	json!({
+		"num_hosts": totals.num_hosts(),
+		"max_size": totals.max_size(),
+		"used_size": totals.used_size(),
+		"num_objects": totals.num_objects(),
+		"total_gets": totals.total_gets(),
+		"miss_ratio": totals.miss_ratio(),
+	})
+}
+
+fn host_value_table(results: &[HostResult], view: &View) -> Result<Table, CommandError> {
+	let mut table = Table::default();
+
+	table.set_header(
+		Row::default()
+			.push("host", Align::Center, Style::Bold)
+			.push("result", Align::Center, Style::Bold)
+			.push("time", Align::Center, Style::Bold),
+	);
+
+	for result in results {
+		let message = match result.result() {
+			Ok(Response::Bytes(value)) => value_cell(value, view),
+
+			Ok(Response::Keys(results)) => keys_summary(results),
+			Ok(Response::Policies(status)) => policy_str(status),
+
+			Ok(Response::Resize(resize)) => match resize.is_evicting() {
+				true => format!("{} ({})", resize_text(resize), eviction_text(resize)),
+				false => resize_text(resize),
+			},
+
+			// only the summary of a diff fits in a cell
+			Ok(Response::Diff(values)) => diff_text(values, false)
+				.lines()
+				.next()
+				.unwrap_or_default()
+				.to_owned(),
+
+			Ok(response) => response_text(response, view)?,
+			Err(err) => format!("Err: {err}"),
+		};
+
+		let row = Row::default()
+			.push(result.host(), Align::Left, Style::Normal)
+			.push(message, Align::Left, Style::Normal)
+			.push(format!("{:?}", result.elapsed()), Align::Right, Style::Normal);
+
+		table.add_row(row);
+	}
+
+	Ok(table)
+}
+
+fn host_status_table(results: &[HostResult], totals: &StatusTotals) -> Table {
+	let mut table = Table::default();
+
+	table.set_header(
+		Row::default()
+			.push("host", Align::Center, Style::Bold)
+			.push("max_size", Align::Center, Style::Bold)
+			.push("used_size", Align::Center, Style::Bold)
+			.push("num_objects", Align::Center, Style::Bold)
+			.push("total_gets", Align::Center, Style::Bold)
+			.push("miss_ratio", Align::Center, Style::Bold)
+			.push("policy", Align::Center, Style::Bold),
+	);
+
+	for result in results {
+		let row = match result.result() {
+			Ok(Response::Status(status)) => status_row(
+				result.host(),
+				status.max_size(),
+				status.used_size(),
+				status.num_objects(),
+				status.total_gets(),
+				status.miss_ratio(),
+				&policy_str(status),
+			),
+
+			Ok(_) => continue,
+
+			Err(err) => Row::default()
+				.push(result.host(), Align::Left, Style::Normal)
+				.push(format!("Err: {err}"), Align::Left, Style::Normal)
+				.blank()
+				.blank()
+				.blank()
+				.blank()
+				.blank(),
+		};
+
+		table.add_row(row);
+	}
+
+	table.add_spacer();
+
+	table.add_row(status_row(
+		&format!("total ({}/{})", totals.num_hosts(), results.len()),
+		totals.max_size(),
+		totals.used_size(),
+		totals.num_objects(),
+		totals.total_gets(),
+		totals.miss_ratio(),
+		"",
+	));
+
+	table
+}
+
+fn status_row(
+	name: &str,
+	max_size: u64,
+	used_size: u64,
+	num_objects: u64,
+	total_gets: u64,
+	miss_ratio: f64,
+	policy: &str,
+) -> Row {
+	Row::default()
+		.push(name, Align::Left, Style::Normal)
+		.push(fmt::memory(max_size, Some(2)), Align::Right, Style::Normal)
+		.push(fmt::memory(used_size, Some(2)), Align::Right, Style::Normal)
+		.push(fmt::number(num_objects), Align::Right, Style::Normal)
+		.push(fmt::number(total_gets), Align::Right, Style::Normal)
+		.push(format!("{miss_ratio:.3}"), Align::Right, Style::Normal)
+		.push(policy, Align::Left, Style::Normal)
+}
+
+#[cfg(test)]
+mod tests {
+	use paper_client::PaperValue;
+	use serde_json::json;
+
+	use crate::{
+		command::{
+			client::Response,
+			error::CommandError,
+			multi::{KeyResult, KeyResults},
+			view::View,
+		},
+		output::{response_text, result_json, value_cell, write_raw},
+	};
+
+	fn view(tokens: &[&str]) -> View {
+		let tokens = tokens
+			.iter()
+			.map(|token| token.to_string())
+			.collect::<Vec<_>>();
+
+		View::parse(&tokens, "get").unwrap()
+	}
+
+	fn bytes(buf: &[u8]) -> Response {
+		Response::Bytes(PaperValue::from(buf))
+	}
+
+	fn text(response: &Response, tokens: &[&str]) -> Result<String, CommandError> {
+		response_text(response, &view(tokens))
+	}
+
+	#[test]
+	fn it_renders_simple_responses() {
+		assert_eq!(text(&Response::Done, &[]), Ok("done".into()));
+		assert_eq!(text(&Response::Bool(true), &[]), Ok("true".into()));
+		assert_eq!(text(&Response::Size(2048), &[]), Ok("2.00 KiB (2048 B)".into()));
+	}
+
+	#[test]
+	fn it_renders_values() {
+		assert_eq!(text(&bytes("日本".as_bytes()), &[]), Ok("日本".into()));
+		assert_eq!(
+			text(&bytes(b"hi"), &["--hex"]),
+			Ok(format!("2.00 B (2 B)\n00000000: 6869{}hi", " ".repeat(37))),
+		);
+
+		assert_eq!(text(&bytes(b"hi"), &["--base64"]), Ok("aGk=".into()));
+		assert_eq!(text(&bytes(&[42, 0]), &["as", "u16le"]), Ok("42".into()));
+		assert_eq!(text(&bytes(&[0xff, 0xfe]), &["as", "i16be"]), Ok("-2".into()));
+
+		assert_eq!(
+			text(&bytes(&[42]), &["as", "u16le"]),
+			Err(CommandError::InvalidValueLength("u16le".into(), 2, 1)),
+		);
+	}
+
+	#[test]
+	fn it_renders_binary_values_as_a_hexdump() {
+		let text = text(&bytes(&[0, 1, 0xff]), &[]).unwrap();
+		assert!(text.starts_with("3.00 B (3 B)\n00000000: "), "{text}");
+	}
+
+	#[test]
+	fn it_renders_json_values() {
+		let value = bytes(br#"{"profile":{"name":"kia"},"items":[1,2]}"#);
+
+		assert_eq!(text(&value, &[".profile.name"]), Ok("\x1B[32m\"kia\"\x1B[0m".into()));
+
+		let value = PaperValue::from(r#"{"profile":{"name":"kia"},"items":[1,2]}"#);
+		assert_eq!(value_cell(&value, &view(&[".items"])), "[1,2]");
+	}
+
+	#[test]
+	fn it_renders_value_cells() {
+		assert_eq!(value_cell(&PaperValue::from("hi"), &view(&[])), "hi");
+		assert_eq!(value_cell(&PaperValue::from(&[0, 1][..]), &view(&[])), "<2 B binary>");
+		assert_eq!(value_cell(&PaperValue::from("hi"), &view(&["--hex"])), "6869");
+	}
+
+	#[test]
+	fn it_renders_key_results() {
+		let mut results = KeyResults::new("found");
+
+		results.push(KeyResult::new("a".into(), Ok(bytes(b"1"))));
+		results.push(KeyResult::new("b".into(), Ok(Response::Bool(false))));
+		results.push(KeyResult::new("c".into(), Err(CommandError::NotConnected)));
+
+		let text = text(&Response::Keys(results), &[]).unwrap();
+		assert!(text.starts_with("1 found, 1 missing, 1 errors\n"), "{text}");
+	}
+
+	#[test]
+	fn it_renders_json_results() {
+		let result = |result, tokens| result_json("host:3145", &result, &view(tokens));
+
+		assert_eq!(
+			result(Ok(Response::Bool(true)), &[]),
+			json!({"host": "host:3145", "ok": true, "value": true}),
+		);
+
+		assert_eq!(
+			result(Ok(bytes(&[0xff, 0])), &[]),
+			json!({"host": "host:3145", "ok": true, "value": "/wA=", "encoding": "base64"}),
+		);
+
+		assert_eq!(
+			result(Ok(bytes(br#"{"a":[1]}"#)), &[".a[0]"]),
+			json!({"host": "host:3145", "ok": true, "value": 1}),
+		);
+
+		assert_eq!(
+			result(Ok(bytes(&[1, 0, 0, 0])), &["as", "u32le"]),
+			json!({"host": "host:3145", "ok": true, "value": 1}),
+		);
+
+		assert_eq!(
+			result(Err(CommandError::NotConnected), &[]),
+			json!({"host": "host:3145", "ok": false, "error": "not connected to a server"}),
+		);
+	}
+
+	#[test]
+	fn it_separates_raw_values() {
+		let mut out = Vec::new();
+
+		write_raw(&mut out, b"done").unwrap();
+		write_raw(&mut out, b"hello").unwrap();
+
+		assert_eq!(out, b"done\nhello\n");
+	}
+}
@@ -0,0 +1,338 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::fmt::Write;
+
+/// The number of unchanged lines shown around each change.
+const CONTEXT_LINES: usize = 3;
+
+/// The maximum number of line edits searched for before giving up, which
+/// bounds the time and memory used to diff very different values.
+const MAX_EDITS: usize = 2000;
+
+#[derive(Clone, Copy)]
+enum Edit<'a> {
+	Equal(&'a str),
+	Delete(&'a str),
+	Insert(&'a str),
+}
+
+/// The differences between two text values.
+pub enum TextDiff {
+	Identical,
+
+	/// The lines which differ in the unified diff format.
+	Lines(String),
+
+	/// The values differ in too many places to be shown, starting from the
+	/// line with the supplied (one-based) number.
+	TooLarge(usize),
+
+	/// The lines of the values are the same, but they end differently
+	/// (e.g., only one value ends with a new line).
+	LineEndings,
+}
+
+impl TextDiff {
+	/// Compares the texts line by line. The headers of each text are shown
+	/// above the differing lines, which are coloured if requested.
+	pub fn new(a: &str, b: &str, headers: (&str, &str), colour: bool) -> Self {
+		if a == b {
+			return TextDiff::Identical;
+		}
+
+		let a_lines = a.lines().collect::<Vec<_>>();
+		let b_lines = b.lines().collect::<Vec<_>>();
+
+		if a_lines == b_lines {
+			return TextDiff::LineEndings;
+		}
+
+		match edits(&a_lines, &b_lines) {
+			Some(edits) => TextDiff::Lines(unified(&edits, headers, colour)),
+
+			None => {
+				let line = a_lines
+					.iter()
+					.zip(&b_lines)
+					.take_while(|(a, b)| a == b)
+					.count();
+
+				TextDiff::TooLarge(line + 1)
+			},
+		}
+	}
+}
+
+/// Returns the offset of the first byte which differs between the buffers,
+/// or `None` if they are identical. If one buffer is a prefix of the other,
+/// the offset is the length of the shorter buffer.
+pub fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+	let offset = a
+		.iter()
+		.zip(b)
+		.take_while(|(a, b)| a == b)
+		.count();
+
+	match offset == a.len() && offset == b.len() {
+		true => None,
+		false => Some(offset),
+	}
+}
+
+/// Finds the shortest list of edits which turns the first list of lines
+/// into the second using Myers' algorithm. Returns `None` if more than the
+/// maximum number of edits are needed.
+fn edits<'a>(a: &[&'a str], b: &[&'a str]) -> Option<Vec<Edit<'a>>> {
+	let n = a.len() as isize;
+	let m = b.len() as isize;
+
+	let max = (a.len() + b.len()).min(MAX_EDITS) as isize;
+	let offset = max + 1;
+
+	// the furthest x reached on each diagonal k, indexed by k + offset
+	let mut v = vec![0isize; 2 * offset as usize + 1];
+
+	// the part of v used by each round, which is needed to backtrack
+	let mut trace: Vec<Vec<isize>> = Vec::new();
+
+	let is_down = |v: &[isize], k: isize, d: isize| {
+		k == -d || (k != d && v[(k - 1 + offset) as usize] < v[(k + 1 + offset) as usize])
+	};
+
+	let mut is_done = false;
+
+	for d in 0..=max {
+		trace.push(v[(offset - d - 1) as usize..=(offset + d + 1) as usize].to_vec());
+
+		for k in (-d..=d).step_by(2) {
+			let mut x = match is_down(&v, k, d) {
+				true => v[(k + 1 + offset) as usize],
+				false => v[(k - 1 + offset) as usize] + 1,
+			};
+
+			let mut y = x - k;
+
+			while x < n && y < m && a[x as usize] == b[y as usize] {
+				x += 1;
+				y += 1;
+			}
+
+			v[(k + offset) as usize] = x;
+
+			if x >= n && y >= m {
+				is_done = true;
+				break;
+			}
+		}
+
+		if is_done {
+			break;
+		}
+	}
+
+	if !is_done {
+		return None;
+	}
+
+	let mut edits = Vec::new();
+
+	let mut x = n;
+	let mut y = m;
+
+	for (d, round) in trace.iter().enumerate().rev() {
+		let d = d as isize;
+		let k = x - y;
+
+		// the trace of round d only covers diagonals -d - 1 to d + 1
+		let at = |k: isize| round[(k + d + 1) as usize];
+
+		let prev_k = match k == -d || (k != d && at(k - 1) < at(k + 1)) {
+			true => k + 1,
+			false => k - 1,
+		};
+
+		let prev_x = at(prev_k);
+		let prev_y = prev_x - prev_k;
+
+		while x > prev_x && y > prev_y {
+			edits.push(Edit::Equal(a[x as usize - 1]));
+
+			x -= 1;
+			y -= 1;
+		}
+
+		if d > 0 {
+			match x == prev_x {
+				true => edits.push(Edit::Insert(b[y as usize - 1])),
+				false => edits.push(Edit::Delete(a[x as usize - 1])),
+			}
+		}
+
+		x = prev_x;
+		y = prev_y;
+	}
+
+	edits.reverse();
+
+	Some(edits)
+}
+
+/// Formats the edits in the unified diff format, with only the changed
+/// lines and a few lines of context around them.
+fn unified(edits: &[Edit], (a_header, b_header): (&str, &str), colour: bool) -> String {
+	let paint = |code: &str, text: &str| match colour {
+		true => format!("\x1B[{code}m{text}\x1B[0m"),
+		false => text.to_owned(),
+	};
+
+	let mut output = format!(
+		"{}\n{}",
+		paint("1", &format!("--- {a_header}")),
+		paint("1", &format!("+++ {b_header}")),
+	);
+
+	for (start, end) in hunks(edits) {
+		let a_start = edits[..start]
+			.iter()
+			.filter(|edit| !matches!(edit, Edit::Insert(_)))
+			.count();
+
+		let b_start = edits[..start]
+			.iter()
+			.filter(|edit| !matches!(edit, Edit::Delete(_)))
+			.count();
+
+		let hunk = &edits[start..end];
+
+		let a_len = hunk
+			.iter()
+			.filter(|edit| !matches!(edit, Edit::Insert(_)))
+			.count();
+
+		let b_len = hunk
+			.iter()
+			.filter(|edit| !matches!(edit, Edit::Delete(_)))
+			.count();
+
+		let header = format!("@@ -{},{a_len} +{},{b_len} @@", a_start + 1, b_start + 1);
+		let _ = write!(output, "\n{}", paint("36", &header));
+
+		for edit in hunk {
+			let line = match edit {
+				Edit::Equal(line) => format!(" {line}"),
+				Edit::Delete(line) => paint("31", &format!("-{line}")),
+				Edit::Insert(line) => paint("32", &format!("+{line}")),
+			};
+
+			let _ = write!(output, "\n{line}");
+		}
+	}
+
+	output
+}
+
+/// Returns the ranges of edits which are shown, each of which includes the
+/// changed lines and the context around them.
+fn hunks(edits: &[Edit]) -> Vec<(usize, usize)> {
+	let mut hunks: Vec<(usize, usize)> = Vec::new();
+
+	for (index, edit) in edits.iter().enumerate() {
+		if matches!(edit, Edit::Equal(_)) {
+			continue;
+		}
+
+		let start = index.saturating_sub(CONTEXT_LINES);
+		let end = (index + CONTEXT_LINES + 1).min(edits.len());
+
+		match hunks.last_mut() {
+			Some(last) if start <= last.1 => last.1 = end,
+			_ => hunks.push((start, end)),
+		}
+	}
+
+	hunks
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::output::diff::{Edit, MAX_EDITS, TextDiff, edits, first_difference};
+
+	/// Returns the edits between the texts as lines of a unified diff.
+	fn diff(a: &str, b: &str) -> Option<Vec<String>> {
+		let a = a.lines().collect::<Vec<_>>();
+		let b = b.lines().collect::<Vec<_>>();
+
+		let lines = edits(&a, &b)?
+			.into_iter()
+			.map(|edit| match edit {
+				Edit::Equal(line) => format!(" {line}"),
+				Edit::Delete(line) => format!("-{line}"),
+				Edit::Insert(line) => format!("+{line}"),
+			})
+			.collect();
+
+		Some(lines)
+	}
+
+	#[test]
+	fn it_finds_no_edits_in_equal_texts() {
+		assert_eq!(diff("", ""), Some(vec![]));
+		assert_eq!(diff("a\nb", "a\nb"), Some(vec![" a".into(), " b".into()]));
+	}
+
+	#[test]
+	fn it_finds_insertions_and_deletions() {
+		assert_eq!(diff("", "a"), Some(vec!["+a".into()]));
+		assert_eq!(diff("a", ""), Some(vec!["-a".into()]));
+		assert_eq!(diff("a\nc", "a\nb\nc"), Some(vec![" a".into(), "+b".into(), " c".into()]));
+		assert_eq!(diff("a\nb\nc", "a\nc"), Some(vec![" a".into(), "-b".into(), " c".into()]));
+	}
+
+	#[test]
+	fn it_finds_the_shortest_edits() {
+		let lines = diff("a\nb\nc\na\nb\nb\na", "c\nb\na\nb\na\nc").unwrap();
+		let num_changes = lines
+			.iter()
+			.filter(|line| !line.starts_with(' '))
+			.count();
+
+		assert_eq!(num_changes, 5);
+	}
+
+	#[test]
+	fn it_gives_up_on_very_different_texts() {
+		let a = (0..MAX_EDITS).map(|n| format!("a{n}")).collect::<Vec<_>>().join("\n");
+		let b = (0..MAX_EDITS).map(|n| format!("b{n}")).collect::<Vec<_>>().join("\n");
+
+		assert_eq!(diff(&a, &b), None);
+		assert!(matches!(TextDiff::new(&a, &b, ("a", "b"), false), TextDiff::TooLarge(1)));
+	}
+
+	#[test]
+	fn it_formats_unified_diffs() {
+		let TextDiff::Lines(lines) = TextDiff::new("a\nb\nc", "a\nx\nc", ("one", "two"), false) else {
+			panic!("expected a line diff");
+		};
+
+		assert_eq!(lines, "--- one\n+++ two\n@@ -1,3 +1,3 @@\n a\n-b\n+x\n c");
+	}
+
+	#[test]
+	fn it_compares_line_endings() {
+		assert!(matches!(TextDiff::new("a", "a", ("a", "b"), false), TextDiff::Identical));
+		assert!(matches!(TextDiff::new("a", "a\n", ("a", "b"), false), TextDiff::LineEndings));
+	}
+
+	#[test]
+	fn it_finds_the_first_difference() {
+		assert_eq!(first_difference(b"abc", b"abc"), None);
+		assert_eq!(first_difference(b"abc", b"abd"), Some(2));
+		assert_eq!(first_difference(b"ab", b"abc"), Some(2));
+		assert_eq!(first_difference(b"", b"a"), Some(0));
+	}
+}
@@ -0,0 +1,65 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::fmt::Write;
+
+use kwik::fmt;
+
+const BYTES_PER_LINE: usize = 16;
+const MAX_BYTES: usize = 1024;
+
+/// Formats the buffer as an `xxd`-style dump of offsets, hex bytes and their
+/// printable ASCII characters. Buffers longer than a fixed limit are
+/// truncated.
+pub fn hexdump(buf: &[u8]) -> String {
+	let mut output = format!("{} ({} B)", fmt::memory(buf.len(), Some(2)), buf.len());
+
+	for (index, line) in buf
+		.chunks(BYTES_PER_LINE)
+		.take(MAX_BYTES / BYTES_PER_LINE)
+		.enumerate()
+	{
+		let _ = write!(output, "\n{:08x}: ", index * BYTES_PER_LINE);
+
+		for position in 0..BYTES_PER_LINE {
+			match line.get(position) {
+				Some(byte) => {
+					let _ = write!(output, "{byte:02x}");
+				},
+
+				None => output.push_str("  "),
+			}
+
+			if position % 2 == 1 {
+				output.push(' ');
+			}
+		}
+
+		output.push(' ');
+
+		for byte in line {
+			match byte.is_ascii_graphic() || *byte == b' ' {
+				true => output.push(*byte as char),
+				false => output.push('.'),
+			}
+		}
+	}
+
+	if buf.len() > MAX_BYTES {
+		let _ = write!(output, "\n... ({} more bytes)", buf.len() - MAX_BYTES);
+	}
+
+	output
+}
+
+/// Formats the buffer as a continuous string of hex bytes.
+pub fn hex(buf: &[u8]) -> String {
+	buf.iter().fold(String::with_capacity(buf.len() * 2), |mut output, byte| {
+		let _ = write!(output, "{byte:02x}");
+		output
+	})
+}
@@ -0,0 +1,126 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use serde_json::Value;
+
+const INDENT: &str = "  ";
+
+const KEY_COLOUR: &str = "\x1B[36m";
+const STRING_COLOUR: &str = "\x1B[32m";
+const NUMBER_COLOUR: &str = "\x1B[33m";
+const LITERAL_COLOUR: &str = "\x1B[35m";
+const RESET: &str = "\x1B[0m";
+
+/// Formats the JSON value, indented over several lines unless compact, and
+/// with syntax colours if requested.
+pub fn format(value: &Value, compact: bool, colour: bool) -> String {
+	let mut output = String::new();
+
+	Formatter {
+		compact,
+		colour,
+	}
+	.write_value(&mut output, value, 0);
+
+	output
+}
+
+struct Formatter {
+	compact: bool,
+	colour:  bool,
+}
+
+impl Formatter {
+	fn write_value(&self, output: &mut String, value: &Value, depth: usize) {
+		match value {
+			Value::Null | Value::Bool(_) => {
+				self.write_coloured(output, &value.to_string(), LITERAL_COLOUR);
+			},
+
+			Value::Number(number) => {
+				self.write_coloured(output, &number.to_string(), NUMBER_COLOUR);
+			},
+
+			Value::String(_) => {
+				self.write_coloured(output, &value.to_string(), STRING_COLOUR);
+			},
+
+			Value::Array(values) => {
+				self.write_container(output, ('[', ']'), values.len(), depth, |output, index| {
+					self.write_value(output, &values[index], depth + 1);
+				});
+			},
+
+			Value::Object(fields) => {
+				let fields = fields.iter().collect::<Vec<_>>();
+
+				self.write_container(output, ('{', '}'), fields.len(), depth, |output, index| {
+					let (key, value) = fields[index];
+
+					self.write_coloured(output, &Value::from(key.as_str()).to_string(), KEY_COLOUR);
+					match self.compact {
+						true => output.push(':'),
+						false => output.push_str(": "),
+					}
+
+					self.write_value(output, value, depth + 1);
+				});
+			},
+		}
+	}
+
+	/// Writes an array or object, calling the supplied function to write
+	/// each of its elements.
+	fn write_container(
+		&self,
+		output: &mut String,
+		(open, close): (char, char),
+		len: usize,
+		depth: usize,
+		write_element: impl Fn(&mut String, usize),
+	) {
+		output.push(open);
+
+		if len == 0 {
+			output.push(close);
+			return;
+		}
+
+		for index in 0..len {
+			if index > 0 {
+				output.push(',');
+			}
+
+			self.write_new_line(output, depth + 1);
+			write_element(output, index);
+		}
+
+		self.write_new_line(output, depth);
+		output.push(close);
+	}
+
+	fn write_new_line(&self, output: &mut String, depth: usize) {
+		if self.compact {
+			return;
+		}
+
+		output.push('\n');
+		output.push_str(&INDENT.repeat(depth));
+	}
+
+	fn write_coloured(&self, output: &mut String, text: &str, colour: &str) {
+		match self.colour {
+			true => {
+				output.push_str(colour);
+				output.push_str(text);
+				output.push_str(RESET);
+			},
+
+			false => output.push_str(text),
+		}
+	}
+}
@@ -0,0 +1,343 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	env,
+	io::{self, IsTerminal, Stdout, Write},
+	process::{Command, Stdio},
+};
+
+use crossterm::{
+	cursor::{Hide, MoveTo, Show},
+	event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+	execute,
+	queue,
+	style::Print,
+	terminal::{
+		self,
+		Clear,
+		ClearType,
+		DisableLineWrap,
+		EnableLineWrap,
+		EnterAlternateScreen,
+		LeaveAlternateScreen,
+	},
+};
+
+/// Returns `true` if the text is being printed to a terminal which is too
+/// short to show all of it at once.
+pub fn is_needed(text: &str) -> bool {
+	if !io::stdout().is_terminal() || !io::stdin().is_terminal() {
+		return false;
+	}
+
+	match terminal::size() {
+		Ok((_, 0)) | Err(_) => false,
+		Ok((_, rows)) => text.lines().count() >= rows as usize,
+	}
+}
+
+/// Shows the text in the pager set in `$PAGER`, or in the built-in pager
+/// if it is not set. This must only be called while the terminal is not in
+/// raw mode (i.e., between reads of the line reader).
+pub fn page(text: &str) -> io::Result<()> {
+	match env::var("PAGER") {
+		Ok(pager) if !pager.trim().is_empty() => page_external(&pager, text),
+		_ => Pager::new(text).run(),
+	}
+}
+
+fn page_external(pager: &str, text: &str) -> io::Result<()> {
+	let mut command = Command::new("sh");
+
+	command
+		.arg("-c")
+		.arg(pager)
+		.stdin(Stdio::piped());
+
+	// colours are only kept by less if it is asked to
+	if env::var_os("LESS").is_none() {
+		command.env("LESS", "FRX");
+	}
+
+	let mut child = command.spawn()?;
+
+	if let Some(mut stdin) = child.stdin.take() {
+		// the pager may exit before reading all of the text
+		let _ = writeln!(stdin, "{text}");
+	}
+
+	child.wait()?;
+
+	Ok(())
+}
+
+struct Pager<'a> {
+	lines: Vec<&'a str>,
+	plain: Vec<String>,
+
+	top:  usize,
+	rows: usize,
+
+	query:      String,
+	last_match: Option<usize>,
+	message:    Option<String>,
+}
+
+enum Action {
+	Scroll(isize),
+	Top,
+	Bottom,
+
+	Search,
+	NextMatch,
+	PrevMatch,
+
+	Quit,
+	Skip,
+}
+
+impl<'a> Pager<'a> {
+	fn new(text: &'a str) -> Self {
+		let lines = text.lines().collect::<Vec<_>>();
+
+		let plain = lines
+			.iter()
+			.map(|line| strip_escapes(line))
+			.collect();
+
+		let rows = terminal::size()
+			.map(|(_, rows)| rows as usize)
+			.unwrap_or(24);
+
+		Pager {
+			lines,
+			plain,
+
+			top: 0,
+			rows,
+
+			query: String::new(),
+			last_match: None,
+			message: None,
+		}
+	}
+
+	fn run(&mut self) -> io::Result<()> {
+		let mut stdout = io::stdout();
+
+		terminal::enable_raw_mode()?;
+		execute!(stdout, EnterAlternateScreen, DisableLineWrap, Hide)?;
+
+		let result = self.read_events(&mut stdout);
+
+		execute!(stdout, Show, EnableLineWrap, LeaveAlternateScreen)?;
+		terminal::disable_raw_mode()?;
+
+		result
+	}
+
+	fn read_events(&mut self, stdout: &mut Stdout) -> io::Result<()> {
+		loop {
+			self.draw(stdout)?;
+
+			let key_event = match event::read()? {
+				Event::Key(key_event) => key_event,
+
+				Event::Resize(_, rows) => {
+					self.rows = rows as usize;
+					continue;
+				},
+
+				_ => continue,
+			};
+
+			let page = self.page_height() as isize;
+
+			match action(key_event, page) {
+				Action::Scroll(lines) => self.scroll(lines),
+				Action::Top => self.top = 0,
+				Action::Bottom => self.top = self.max_top(),
+
+				Action::Search => {
+					if let Some(query) = self.read_query(stdout)? {
+						self.query = query;
+						self.last_match = None;
+						self.search(true);
+					}
+				},
+
+				Action::NextMatch => self.search(true),
+				Action::PrevMatch => self.search(false),
+
+				Action::Quit => return Ok(()),
+				Action::Skip => {},
+			}
+		}
+	}
+
+	fn draw(&mut self, stdout: &mut Stdout) -> io::Result<()> {
+		queue!(stdout, Clear(ClearType::All))?;
+
+		let visible_lines = self
+			.lines
+			.iter()
+			.skip(self.top)
+			.take(self.page_height());
+
+		for (row, line) in visible_lines.enumerate() {
+			queue!(stdout, MoveTo(0, row as u16), Print(line), Print("\x1B[0m"))?;
+		}
+
+		let status = self.message.take().unwrap_or_else(|| {
+			let last = (self.top + self.page_height()).min(self.lines.len());
+
+			format!(
+				"lines {}-{last} of {} (arrows to scroll, / to search, q to quit)",
+				self.top + 1,
+				self.lines.len(),
+			)
+		});
+
+		self.draw_status(stdout, &status)
+	}
+
+	fn draw_status(&self, stdout: &mut Stdout, status: &str) -> io::Result<()> {
+		queue!(
+			stdout,
+			MoveTo(0, self.page_height() as u16),
+			Clear(ClearType::CurrentLine),
+			Print(format!("\x1B[7m{status}\x1B[0m")),
+		)?;
+
+		stdout.flush()
+	}
+
+	/// Reads a search query on the status line. Returns `None` if the search
+	/// is cancelled.
+	fn read_query(&self, stdout: &mut Stdout) -> io::Result<Option<String>> {
+		let mut query = String::new();
+
+		loop {
+			self.draw_status(stdout, &format!("/{query}"))?;
+
+			let Event::Key(key_event) = event::read()? else {
+				continue;
+			};
+
+			match key_event.code {
+				KeyCode::Enter => return Ok(Some(query)),
+				KeyCode::Esc => return Ok(None),
+
+				KeyCode::Char('c') if key_event.modifiers == KeyModifiers::CONTROL => {
+					return Ok(None);
+				},
+
+				KeyCode::Backspace => {
+					query.pop();
+				},
+
+				KeyCode::Char(c) => query.push(c),
+
+				_ => {},
+			}
+		}
+	}
+
+	/// Moves to the next (or previous) line which contains the query.
+	fn search(&mut self, forward: bool) {
+		if self.query.is_empty() {
+			return;
+		}
+
+		let start = self.last_match.unwrap_or(self.top);
+
+		let is_match = |index: &usize| self.plain[*index].contains(&self.query);
+
+		let found = match (forward, self.last_match) {
+			(true, None) => (start..self.lines.len()).find(is_match),
+			(true, Some(_)) => (start + 1..self.lines.len()).find(is_match),
+			(false, _) => (0..start).rev().find(is_match),
+		};
+
+		match found {
+			Some(index) => {
+				self.top = index.min(self.max_top());
+				self.last_match = Some(index);
+				self.message = Some(format!("match on line {}", index + 1));
+			},
+
+			None => self.message = Some(format!("pattern not found: {}", self.query)),
+		}
+	}
+
+	fn scroll(&mut self, lines: isize) {
+		self.top = self
+			.top
+			.saturating_add_signed(lines)
+			.min(self.max_top());
+	}
+
+	/// Returns the number of rows available for text, leaving the last row
+	/// for the status line.
+	fn page_height(&self) -> usize {
+		self.rows.saturating_sub(1).max(1)
+	}
+
+	fn max_top(&self) -> usize {
+		self.lines
+			.len()
+			.saturating_sub(self.page_height())
+	}
+}
+
+fn action(key_event: KeyEvent, page: isize) -> Action {
+	if key_event.code == KeyCode::Char('c') && key_event.modifiers == KeyModifiers::CONTROL {
+		return Action::Quit;
+	}
+
+	match key_event.code {
+		KeyCode::Down | KeyCode::Enter | KeyCode::Char('j') => Action::Scroll(1),
+		KeyCode::Up | KeyCode::Char('k') => Action::Scroll(-1),
+
+		KeyCode::PageDown | KeyCode::Char(' ' | 'f') => Action::Scroll(page),
+		KeyCode::PageUp | KeyCode::Char('b') => Action::Scroll(-page),
+
+		KeyCode::Home | KeyCode::Char('g') => Action::Top,
+		KeyCode::End | KeyCode::Char('G') => Action::Bottom,
+
+		KeyCode::Char('/') => Action::Search,
+		KeyCode::Char('n') => Action::NextMatch,
+		KeyCode::Char('N') => Action::PrevMatch,
+
+		KeyCode::Esc | KeyCode::Char('q') => Action::Quit,
+
+		_ => Action::Skip,
+	}
+}
+
+/// Removes the colour escape sequences from the line so that searches only
+/// match its visible text.
+fn strip_escapes(line: &str) -> String {
+	let mut plain = String::with_capacity(line.len());
+	let mut chars = line.chars();
+
+	while let Some(c) = chars.next() {
+		if c != '\x1B' {
+			plain.push(c);
+			continue;
+		}
+
+		for c in chars.by_ref() {
+			if c.is_ascii_alphabetic() {
+				break;
+			}
+		}
+	}
+
+	plain
+}
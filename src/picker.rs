@@ -0,0 +1,100 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::io::{self, IsTerminal, Stdout, Write};
+
+use crossterm::{
+	cursor::{Hide, MoveToColumn, MoveToPreviousLine, Show},
+	event::{self, Event, KeyCode, KeyModifiers},
+	execute,
+	queue,
+	style::Print,
+	terminal::{self, Clear, ClearType},
+};
+
+/// Returns `true` if the user can pick from a list, which requires both
+/// stdin and stdout to be terminals.
+pub fn is_available() -> bool {
+	io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+/// Shows the items below the title as a list in which one item is selected,
+/// starting with the supplied one. The selection is moved with the arrow
+/// keys and chosen with enter. Returns the index of the chosen item, or
+/// `None` if the list is closed with escape, `q` or Ctrl-C. The list is
+/// removed from the terminal once it is closed.
+pub fn pick(title: &str, items: &[String], selected: usize) -> io::Result<Option<usize>> {
+	if items.is_empty() {
+		return Ok(None);
+	}
+
+	let mut stdout = io::stdout();
+	let mut selected = selected.min(items.len() - 1);
+
+	terminal::enable_raw_mode()?;
+	execute!(stdout, Hide, Print(format!("{title}\r\n")))?;
+
+	let result = loop {
+		if let Err(err) = draw(&mut stdout, items, selected) {
+			break Err(err);
+		}
+
+		let key_event = match event::read() {
+			Ok(Event::Key(key_event)) => key_event,
+			Ok(_) => continue,
+			Err(err) => break Err(err),
+		};
+
+		if key_event.code == KeyCode::Char('c') && key_event.modifiers == KeyModifiers::CONTROL {
+			break Ok(None);
+		}
+
+		match key_event.code {
+			KeyCode::Up | KeyCode::Char('k') => selected = selected.saturating_sub(1),
+			KeyCode::Down | KeyCode::Char('j') => selected = (selected + 1).min(items.len() - 1),
+
+			KeyCode::Home => selected = 0,
+			KeyCode::End => selected = items.len() - 1,
+
+			KeyCode::Enter => break Ok(Some(selected)),
+			KeyCode::Esc | KeyCode::Char('q') => break Ok(None),
+
+			_ => {},
+		}
+
+		// the next draw starts from the first item
+		if let Err(err) = execute!(stdout, MoveToPreviousLine(items.len() as u16)) {
+			break Err(err);
+		}
+	};
+
+	// move back above the title to remove the list
+	execute!(
+		stdout,
+		MoveToPreviousLine(items.len() as u16 + 1),
+		MoveToColumn(0),
+		Clear(ClearType::FromCursorDown),
+		Show,
+	)?;
+
+	terminal::disable_raw_mode()?;
+
+	result
+}
+
+fn draw(stdout: &mut Stdout, items: &[String], selected: usize) -> io::Result<()> {
+	for (index, item) in items.iter().enumerate() {
+		queue!(stdout, Clear(ClearType::CurrentLine))?;
+
+		match index == selected {
+			true => queue!(stdout, Print(format!("\x1B[7m> {item}\x1B[0m\r\n")))?,
+			false => queue!(stdout, Print(format!("  {item}\r\n")))?,
+		}
+	}
+
+	stdout.flush()
+}
@@ -0,0 +1,103 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	collections::HashMap,
+	env,
+	fs,
+	io,
+	path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+	addr::{DEFAULT_PORT, ServerAddr},
+	command::error::CommandError,
+};
+
+#[derive(Clone, Deserialize)]
+pub struct Profile {
+	host: String,
+
+	#[serde(default = "default_port")]
+	port: u16,
+
+	auth: Option<String>,
+
+	/// Asks for confirmation before destructive commands even if `--yes` is
+	/// supplied.
+	#[serde(default)]
+	always_confirm: bool,
+
+	/// Rejects commands which change the cache.
+	#[serde(default)]
+	read_only: bool,
+
+	/// The file in which commands which change the cache are recorded.
+	audit_log: Option<PathBuf>,
+}
+
+impl Profile {
+	pub fn addr(&self) -> Result<ServerAddr, CommandError> {
+		ServerAddr::new(&self.host, self.port)
+	}
+
+	pub fn auth(&self) -> Option<&str> {
+		self.auth.as_deref()
+	}
+
+	pub fn always_confirm(&self) -> bool {
+		self.always_confirm
+	}
+
+	pub fn is_read_only(&self) -> bool {
+		self.read_only
+	}
+
+	pub fn audit_log(&self) -> Option<&Path> {
+		self.audit_log.as_deref()
+	}
+}
+
+/// Finds the profile with the supplied name in the profiles file. Returns
+/// `None` if the profile or the file itself does not exist.
+pub fn find(name: &str) -> Result<Option<Profile>, CommandError> {
+	let Some(path) = path() else {
+		return Ok(None);
+	};
+
+	let data = match fs::read_to_string(path) {
+		Ok(data) => data,
+		Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+		Err(_) => return Err(CommandError::InvalidProfiles),
+	};
+
+	let mut profiles: HashMap<String, Profile> =
+		toml::from_str(&data).map_err(|_| CommandError::InvalidProfiles)?;
+
+	Ok(profiles.remove(name))
+}
+
+/// Returns the path of the profiles file, which can be overridden with the
+/// `PAPER_CLI_PROFILES` environment variable.
+fn path() -> Option<PathBuf> {
+	if let Ok(path) = env::var("PAPER_CLI_PROFILES") {
+		return Some(PathBuf::from(path));
+	}
+
+	let config_dir = match env::var("XDG_CONFIG_HOME") {
+		Ok(dir) => PathBuf::from(dir),
+		Err(_) => PathBuf::from(env::var("HOME").ok()?).join(".config"),
+	};
+
+	Some(config_dir.join("paper-cli").join("profiles.toml"))
+}
+
+fn default_port() -> u16 {
+	DEFAULT_PORT
+}
@@ -0,0 +1,224 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	fs::{self, File, OpenOptions},
+	io::Write,
+	path::Path,
+	time::Duration,
+};
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::command::{CliCommand, ClientCommand, Command, error::CommandError};
+
+/// Replaces auth tokens in recorded input.
+const REDACTED_TOKEN: &str = "********";
+
+/// Appends each line of input in a session to a file, along with the
+/// responses it received and any error, one JSON object per line.
+pub struct Recorder {
+	file: File,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Entry {
+	time:  String,
+	input: String,
+
+	elapsed_ms: f64,
+
+	/// The response from each host the input was sent to, in the format of
+	/// the JSON output.
+	#[serde(default)]
+	responses: Vec<Value>,
+
+	/// The error which prevented the input from being parsed or run.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	error: Option<String>,
+}
+
+impl Recorder {
+	pub fn open(path: &Path) -> Result<Self, CommandError> {
+		let file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(path)
+			.map_err(|_| CommandError::InvalidRecording)?;
+
+		Ok(Recorder {
+			file,
+		})
+	}
+
+	pub fn record(&mut self, entry: &Entry) -> Result<(), CommandError> {
+		let line = serde_json::to_string(entry).map_err(|_| CommandError::RecordingFailed)?;
+
+		writeln!(self.file, "{line}").map_err(|_| CommandError::RecordingFailed)
+	}
+}
+
+impl Entry {
+	pub fn new(
+		input: String,
+		elapsed: Duration,
+		responses: Vec<Value>,
+		error: Option<&CommandError>,
+	) -> Self {
+		Entry {
+			time: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+			input,
+
+			elapsed_ms: elapsed.as_micros() as f64 / 1000.0,
+
+			responses,
+			error: error.map(ToString::to_string),
+		}
+	}
+
+	pub fn input(&self) -> &str {
+		&self.input
+	}
+
+	pub fn responses(&self) -> &[Value] {
+		&self.responses
+	}
+
+	pub fn error(&self) -> Option<&str> {
+		self.error.as_deref()
+	}
+
+	/// Returns `true` if the input received the same responses and error as
+	/// the other entry. The hosts which sent the responses are ignored, so
+	/// that a session can be replayed against a different server.
+	pub fn matches(&self, other: &Entry) -> bool {
+		let responses = |entry: &Entry| {
+			entry
+				.responses
+				.iter()
+				.map(without_host)
+				.collect::<Vec<_>>()
+		};
+
+		responses(self) == responses(other) && self.error == other.error
+	}
+}
+
+/// Reads the entries of a recording.
+pub fn read(path: &Path) -> Result<Vec<Entry>, CommandError> {
+	let data = fs::read_to_string(path).map_err(|_| CommandError::InvalidRecording)?;
+
+	data.lines()
+		.filter(|line| !line.trim().is_empty())
+		.map(|line| serde_json::from_str(line).map_err(|_| CommandError::InvalidRecording))
+		.collect()
+}
+
+/// Returns the input line as it is recorded, or `None` if it is not recorded
+/// (i.e., it starts or stops recording). The command is `None` if the line
+/// could not be parsed.
+pub fn input(line: &str, command: Option<&Command>) -> Option<String> {
+	if let Some(Command::Cli(CliCommand::Record(_))) = command {
+		return None;
+	}
+
+	Some(redact(line))
+}
+
+/// Replaces everything after `auth` with a placeholder if the line is an
+/// auth command, whether or not it could be parsed, so that a token is never
+/// written to a recording.
+fn redact(line: &str) -> String {
+	let mut words = line.split_whitespace().peekable();
+	let mut prefix = Vec::new();
+
+	if let Some(word) = words.next_if(|word| word.eq_ignore_ascii_case("dry")) {
+		prefix.push(word);
+	}
+
+	if let Some(word) = words.next_if(|word| word.starts_with('@')) {
+		prefix.push(word);
+	}
+
+	match words.next() {
+		Some(word) if word.trim_matches('"').eq_ignore_ascii_case("auth") => {
+			prefix.push(word);
+
+			if words.next().is_some() {
+				prefix.push(REDACTED_TOKEN);
+			}
+
+			prefix.join(" ")
+		},
+
+		_ => line.to_owned(),
+	}
+}
+
+/// Returns `true` if the command authenticates with a token which was
+/// redacted when it was recorded, so it cannot be replayed.
+pub fn is_redacted(command: &Command) -> bool {
+	auth_token(command) == Some(REDACTED_TOKEN)
+}
+
+fn auth_token(command: &Command) -> Option<&str> {
+	match command {
+		Command::Client(ClientCommand::Auth(token))
+		| Command::Targeted(_, ClientCommand::Auth(token)) => Some(token),
+
+		Command::Dry(command) => auth_token(command),
+
+		_ => None,
+	}
+}
+
+fn without_host(response: &Value) -> Value {
+	let mut response = response.clone();
+
+	if let Some(object) = response.as_object_mut() {
+		object.remove("host");
+	}
+
+	response
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use crate::{
+		command::error::CommandError,
+		record::{Entry, redact},
+	};
+
+	#[test]
+	fn it_redacts_auth_tokens() {
+		assert_eq!(redact("auth s3cret"), "auth ********");
+		assert_eq!(redact("auth a"), "auth ********");
+		assert_eq!(redact("  auth   s3cret extra"), "auth ********");
+		assert_eq!(redact("@cache auth s3cret"), "@cache auth ********");
+		assert_eq!(redact("dry auth s3cret"), "dry auth ********");
+		assert_eq!(redact("dry @cache auth \"s3 cret\""), "dry @cache auth ********");
+		assert_eq!(redact("auth"), "auth");
+	}
+
+	#[test]
+	fn it_keeps_other_lines() {
+		assert_eq!(redact("get auth"), "get auth");
+		assert_eq!(redact("set key \"auth a\""), "set key \"auth a\"");
+	}
+
+	#[test]
+	fn it_records_the_error_a_command_failed_with() {
+		let err = CommandError::CommandFailed("the key was not found in the cache".to_owned());
+		let entry = Entry::new("get key".to_owned(), Duration::ZERO, Vec::new(), Some(&err));
+
+		assert_eq!(entry.error(), Some("the key was not found in the cache"));
+	}
+}
@@ -0,0 +1,103 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::collections::BTreeMap;
+
+use fasthash::xx;
+
+const VIRTUAL_NODES: usize = 160;
+
+/// A consistent hash ring which maps keys to nodes. Each node is placed on
+/// the ring at several points (virtual nodes) so that keys are distributed
+/// evenly and only a fraction of keys move when a node is added or removed.
+pub struct HashRing {
+	ring: BTreeMap<u64, String>,
+}
+
+impl HashRing {
+	pub fn new<'a>(nodes: impl IntoIterator<Item = &'a str>) -> Self {
+		let mut ring = BTreeMap::new();
+
+		for node in nodes {
+			for index in 0..VIRTUAL_NODES {
+				let hash = xx::hash64(format!("{node}#{index}"));
+				ring.insert(hash, node.to_owned());
+			}
+		}
+
+		HashRing {
+			ring,
+		}
+	}
+
+	/// Returns the node which owns the supplied key, which is the first node
+	/// clockwise from the key's position on the ring.
+	pub fn node(&self, key: &str) -> Option<&str> {
+		let hash = xx::hash64(key);
+
+		self.ring
+			.range(hash..)
+			.next()
+			.or_else(|| self.ring.iter().next())
+			.map(|(_, node)| node.as_str())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use crate::shard::HashRing;
+
+	#[test]
+	fn it_has_no_node_when_empty() {
+		assert_eq!(HashRing::new([]).node("key"), None);
+	}
+
+	#[test]
+	fn it_maps_keys_to_the_same_node() {
+		let ring = HashRing::new(["a", "b", "c"]);
+		let other_ring = HashRing::new(["c", "a", "b"]);
+
+		for index in 0..100 {
+			let key = format!("key:{index}");
+			assert_eq!(ring.node(&key), other_ring.node(&key));
+		}
+
+		assert_eq!(HashRing::new(["a"]).node("日本"), Some("a"));
+	}
+
+	#[test]
+	fn it_distributes_keys_evenly() {
+		let ring = HashRing::new(["a", "b", "c"]);
+		let mut counts: HashMap<&str, usize> = HashMap::new();
+
+		for index in 0..3000 {
+			let node = ring.node(&format!("key:{index}")).unwrap();
+			*counts.entry(node).or_default() += 1;
+		}
+
+		assert_eq!(counts.len(), 3);
+		assert!(counts.values().all(|count| (700..=1300).contains(count)), "{counts:?}");
+	}
+
+	#[test]
+	fn it_moves_few_keys_when_a_node_is_added() {
+		let ring = HashRing::new(["a", "b", "c"]);
+		let new_ring = HashRing::new(["a", "b", "c", "d"]);
+
+		for index in 0..1000 {
+			let key = format!("key:{index}");
+			let new_node = new_ring.node(&key).unwrap();
+
+			// a key only moves to the new node
+			if new_node != "d" {
+				assert_eq!(ring.node(&key), Some(new_node));
+			}
+		}
+	}
+}